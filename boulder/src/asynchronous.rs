@@ -0,0 +1,160 @@
+//! Builders and generators whose defaults need to be awaited.
+
+use std::future::Future;
+
+use crate::{BuildableBase, GeneratableBase};
+
+/// Something which can create a default object of some type,
+/// awaiting as it does so.
+///
+/// This is the asynchronous counterpart of [`Builder`](crate::Builder).
+/// The [`macro@Buildable`](crate::Buildable) derive macro implements
+/// it for its builder when the `"async"` feature is enabled. Fields
+/// with a `default_async` attribute are filled in by
+/// [`build`](AsyncBuilder::build), unless they have been set
+/// explicitly.
+///
+/// A type with a `default_async` field has no synchronous builder at
+/// all: it doesn't implement [`Buildable`](crate::Buildable) or
+/// [`Builder`](crate::Builder), so there is no `build_many` or
+/// `create` for it, and it can't be used as a `buildable` field of a
+/// type without async defaults. Wrappers such as `Option<Foo>` still
+/// pass through to [`AsyncBuildable`]. Types without `default_async`
+/// fields get both builders; when both traits are in scope, name the
+/// one you mean, as in `AsyncBuilder::build(Foo::async_builder())`.
+///
+/// The returned future borrows nothing, but it is not `Send`,
+/// since the builder may hold values which aren't; await it on the
+/// task which built it. Nothing here depends on a particular runtime:
+/// the future can be driven by any executor.
+///
+/// Example
+/// ```rust
+/// use boulder::{AsyncBuildable, AsyncBuilder, Buildable};
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::sync::Arc;
+/// use std::task::{Context, Poll, Wake, Waker};
+///
+/// async fn allocate_id() -> u64 {
+///     42
+/// }
+///
+/// #[derive(Buildable)]
+/// struct Order {
+///     #[boulder(default_async = allocate_id())]
+///     id: u64,
+///     #[boulder(default = 1u32)]
+///     quantity: u32,
+/// }
+///
+/// // Any executor will do; this one just polls until it's done.
+/// struct Noop;
+/// impl Wake for Noop {
+///     fn wake(self: Arc<Self>) {}
+/// }
+/// fn block_on<F: Future>(future: F) -> F::Output {
+///     let waker = Waker::from(Arc::new(Noop));
+///     let mut cx = Context::from_waker(&waker);
+///     let mut future = pin!(future);
+///     loop {
+///         if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+///             return value;
+///         }
+///     }
+/// }
+///
+/// let order = block_on(async { Order::async_builder().quantity(3u32).build().await });
+/// assert_eq!(order.id, 42);
+/// assert_eq!(order.quantity, 3);
+/// ```
+///
+/// There is no synchronous builder for a type with a `default_async`
+/// field:
+/// ```compile_fail
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// struct Order {
+///     #[boulder(default_async = async { 42u64 })]
+///     id: u64,
+/// }
+///
+/// let order = Order::builder().build();
+/// ```
+pub trait AsyncBuilder {
+    /// The output type.
+    type Result;
+    /// Create the final object, once any asynchronous defaults have
+    /// been awaited.
+    fn build(self) -> impl Future<Output = Self::Result>;
+}
+
+/// A type that has an associated default [`AsyncBuilder`].
+///
+/// This is the asynchronous counterpart of
+/// [`Buildable`](crate::Buildable), and is implemented for every
+/// [`BuildableBase`] whose builder is an [`AsyncBuilder`], including
+/// wrappers such as `Option<Foo>`. Unlike `Buildable`, it covers
+/// types with `default_async` fields.
+pub trait AsyncBuildable: Sized {
+    /// A default choice of [`AsyncBuilder`] for this type.
+    type Builder: AsyncBuilder<Result = Self>;
+    /// Create a new default builder.
+    fn async_builder() -> Self::Builder;
+}
+
+impl<T> AsyncBuildable for T
+where
+    T: BuildableBase,
+    <T as BuildableBase>::Builder<T>: AsyncBuilder<Result = T>,
+{
+    type Builder = <T as BuildableBase>::Builder<T>;
+    fn async_builder() -> Self::Builder {
+        <T as BuildableBase>::builder_for::<T>()
+    }
+}
+
+/// A source of objects of some type, awaiting as it creates each one.
+///
+/// This is the asynchronous counterpart of
+/// [`Generator`](crate::Generator). The
+/// [`macro@Generatable`](crate::Generatable) derive macro implements
+/// it for its generator when the `"async"` feature is enabled; as for
+/// [`AsyncBuilder`], a derived generator with `default_async` fields
+/// doesn't implement [`Generator`](crate::Generator), so there is no
+/// [`Generatable`](crate::Generatable) or `create` for its type.
+///
+/// The future returned by [`generate`](AsyncGenerator::generate)
+/// borrows the generator until it completes, and is not `Send`.
+pub trait AsyncGenerator {
+    /// The output type.
+    type Output;
+    /// Make a new object, once any asynchronous defaults have been
+    /// awaited.
+    fn generate(&mut self) -> impl Future<Output = Self::Output>;
+}
+
+/// A type that has an associated default [`AsyncGenerator`].
+///
+/// This is the asynchronous counterpart of
+/// [`Generatable`](crate::Generatable), and is implemented for every
+/// [`GeneratableBase`] whose generator is an [`AsyncGenerator`],
+/// including wrappers such as `Option<Foo>`.
+pub trait AsyncGeneratable: Sized {
+    /// A default choice of [`AsyncGenerator`] for this type.
+    type Generator: AsyncGenerator<Output = Self>;
+    /// Return this type's default generator.
+    fn async_generator() -> Self::Generator;
+}
+
+impl<T> AsyncGeneratable for T
+where
+    T: GeneratableBase,
+    <T as GeneratableBase>::Generator<T>: AsyncGenerator<Output = T>,
+{
+    type Generator = <T as GeneratableBase>::Generator<T>;
+    fn async_generator() -> Self::Generator {
+        <T as GeneratableBase>::generator_for::<T>()
+    }
+}
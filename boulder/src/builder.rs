@@ -1,3 +1,5 @@
+use crate::BoulderWrapper;
+
 /// Something which can create a default object of some type.
 ///
 /// The only required function in this trait is
@@ -10,6 +12,12 @@
 /// builder will have a method for each field of the result type, to
 /// customise its value, and will produce a default value for every
 /// field which is not customised.
+#[cfg_attr(
+    feature = "async",
+    diagnostic::on_unimplemented(
+        note = "types with `default_async` fields only have an `AsyncBuilder`: use `async_builder()` and `AsyncBuilder::build`"
+    )
+)]
 pub trait Builder {
     /// The output type.
     type Result;
@@ -44,18 +52,17 @@ pub trait Builder {
 /// A type that has an associated default [`Builder`].
 ///
 /// This trait is implemented via the [`macro@Buildable`] derive
-/// macro. It cannot be directly implemented because the library
-/// itself provides a blanket implementation from a more complex
-/// underlying trait `MiniBuildable`, which is not currently
-/// documented.
-///
-/// This restriction may be removed in a future version; much of the
-/// complexity in this module stems from lacking generic associated
-/// types on stable.
-pub trait Buildable: Sized
-where
-    Self: guts::BoulderBase,
-{
+/// macro. To write a builder by hand, implement [`BuildableBase`]
+/// rather than this trait: the library provides a blanket
+/// implementation of this trait from it, which also covers every
+/// [`BoulderWrapper`](crate::BoulderWrapper) around your type.
+#[cfg_attr(
+    feature = "async",
+    diagnostic::on_unimplemented(
+        note = "types with `default_async` fields only have an `AsyncBuilder`: use `async_builder()` and `AsyncBuilder::build`"
+    )
+)]
+pub trait Buildable: Sized {
     /// A default choice of [`Builder`] for this type.
     type Builder: Builder<Result = Self>;
     /// Create a new default builder.
@@ -76,77 +83,109 @@ where
     fn builder() -> Self::Builder;
 }
 
-pub use boulder_derive::Buildable;
-
-#[doc(hidden)]
-pub mod guts {
-    use super::Buildable;
-
-    use std::cell::{Cell, RefCell};
-    use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
-
-    pub use super::Builder as MiniBuilder;
-
-    pub trait MiniBuildable<T>: Sized {
-        type Builder: MiniBuilder<Result = Self>;
-        fn mini_builder() -> Self::Builder;
-    }
-
-    impl<T> Buildable for T
-    where
-        T: BoulderBase,
-        T: MiniBuildable<<T as BoulderBase>::Base>,
-    {
-        type Builder = <T as MiniBuildable<<T as BoulderBase>::Base>>::Builder;
-        fn builder() -> Self::Builder {
-            <T as MiniBuildable<<T as BoulderBase>::Base>>::mini_builder()
-        }
-    }
-
-    pub trait BoulderBase {
-        type Base;
-    }
-
-    impl<T> BoulderBase for Option<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
-
-    impl<T> BoulderBase for Arc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+/// The family of builders for a type and for wrappers around it.
+///
+/// This is the supported way to give a type a hand-written
+/// builder. The [`macro@Buildable`] derive macro implements this
+/// trait too, so hand-written and derived builders compose in the
+/// same way: any type implementing it is [`Buildable`], with builder
+/// `Self::Builder<Self>`, and so is any
+/// [`BoulderWrapper`](crate::BoulderWrapper) `W` around it, with
+/// builder `Self::Builder<W>`, as long as that type is a
+/// [`Builder`] producing `W`.
+///
+/// A builder which can only produce `Self` may ignore the type
+/// parameter, in which case wrappers will not pass through. A builder
+/// which is generic over its result can support wrappers by building
+/// the wrapped value and then calling
+/// [`BoulderWrapper::wrap`](crate::BoulderWrapper::wrap).
+///
+/// Example
+/// ```rust
+/// use boulder::{Buildable, BuildableBase, Builder, BoulderWrapper};
+/// use std::marker::PhantomData;
+///
+/// struct Foo {
+///   a: i32,
+/// }
+///
+/// struct FooBuilder<T> {
+///   a: i32,
+///   _result: PhantomData<T>,
+/// }
+///
+/// impl<T> FooBuilder<T> {
+///   fn a(mut self, a: i32) -> Self {
+///     self.a = a;
+///     self
+///   }
+/// }
+///
+/// impl Builder for FooBuilder<Foo> {
+///   type Result = Foo;
+///   fn build(self) -> Foo {
+///     Foo { a: self.a }
+///   }
+/// }
+///
+/// impl<W> Builder for FooBuilder<W>
+/// where
+///   W: BoulderWrapper,
+///   FooBuilder<W::Inner>: Builder<Result = W::Inner>,
+/// {
+///   type Result = W;
+///   fn build(self) -> W {
+///     let inner = FooBuilder::<W::Inner> { a: self.a, _result: PhantomData };
+///     W::wrap(inner.build())
+///   }
+/// }
+///
+/// impl BuildableBase for Foo {
+///   type Builder<T> = FooBuilder<T>;
+///   fn builder_for<T>() -> FooBuilder<T> {
+///     FooBuilder { a: 1, _result: PhantomData }
+///   }
+/// }
+///
+/// let f = Foo::builder().a(5).build();
+/// assert_eq!(f.a, 5);
+///
+/// let f = Option::<std::rc::Rc<Foo>>::builder().build();
+/// assert_eq!(f.map(|f| f.a), Some(1));
+/// ```
+pub trait BuildableBase {
+    /// The builder producing `T`, which is either this type or a
+    /// wrapper around it.
+    type Builder<T>;
+    /// Create a new default builder producing `T`.
+    fn builder_for<T>() -> Self::Builder<T>;
+}
 
-    impl<T> BoulderBase for Rc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<W> BuildableBase for W
+where
+    W: BoulderWrapper,
+    <W as BoulderWrapper>::Inner: BuildableBase,
+{
+    type Builder<T> = <<W as BoulderWrapper>::Inner as BuildableBase>::Builder<T>;
+    fn builder_for<T>() -> Self::Builder<T> {
+        <<W as BoulderWrapper>::Inner as BuildableBase>::builder_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for RefCell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<T> Buildable for T
+where
+    T: BuildableBase,
+    <T as BuildableBase>::Builder<T>: Builder<Result = T>,
+{
+    type Builder = <T as BuildableBase>::Builder<T>;
+    fn builder() -> Self::Builder {
+        <T as BuildableBase>::builder_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Cell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+pub use boulder_derive::Buildable;
 
-    impl<T> BoulderBase for Mutex<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+#[doc(hidden)]
+pub mod guts {
+    pub use super::Builder as MiniBuilder;
 }
@@ -0,0 +1,297 @@
+//! Builders and generators for standard library and chrono types.
+
+use crate::{
+    BoulderWrapper, Buildable, BuildableBase, Builder, Cycle, Generatable, GeneratableBase,
+    Generator, Sample,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// The [`Builder`] for standard library and chrono types.
+///
+/// This is what `T::builder()` returns for numbers, [`bool`],
+/// [`char`], [`String`], collections, tuples, arrays and chrono's
+/// date and time types, which means those types can be used with
+/// `#[boulder(buildable)]`. The built value is the type's default,
+/// except for tuples and arrays, which build each of their elements.
+/// Use [`value`](BuiltinBuilder::value) to choose a different one,
+/// or start from a value with [`From`].
+///
+/// Example
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// assert_eq!(u32::builder().build(), 0);
+/// assert_eq!(String::builder().value("hello").build(), "hello");
+/// assert_eq!(<(i32, Vec<u8>)>::builder().build(), (0, Vec::new()));
+/// assert_eq!(Option::<[bool; 2]>::builder().build(), Some([false, false]));
+/// ```
+pub struct BuiltinBuilder<V, T> {
+    value: V,
+    _result: PhantomData<T>,
+}
+
+impl<V, T> BuiltinBuilder<V, T> {
+    fn new(value: V) -> Self {
+        Self {
+            value,
+            _result: PhantomData,
+        }
+    }
+
+    /// Set the value to build.
+    pub fn value<S>(mut self, value: S) -> Self
+    where
+        S: Into<V>,
+    {
+        self.value = value.into();
+        self
+    }
+}
+
+impl<V> From<V> for BuiltinBuilder<V, V> {
+    fn from(value: V) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<V, T> Builder for BuiltinBuilder<V, T>
+where
+    T: FromBuiltin<V>,
+{
+    type Result = T;
+    fn build(self) -> T {
+        T::from_builtin(self.value)
+    }
+}
+
+/// The [`Generator`] for standard library and chrono types.
+///
+/// This is what `T::generator()` returns for numbers, [`bool`],
+/// [`char`], [`String`], collections, tuples, arrays and chrono's
+/// date and time types, which means those types can be used with
+/// `#[boulder(generatable)]`. Successive values vary:
+///
+/// - numbers count up from zero, wrapping on overflow;
+/// - [`bool`] alternates, starting from `false`;
+/// - [`char`] cycles through the lower case ASCII letters;
+/// - [`String`] yields unique labels, `"string-0"`, `"string-1"`, and
+///   so on;
+/// - chrono's dates and times step forward from the Unix epoch, by
+///   one day for dates and date times, and by one minute for times
+///   of day;
+/// - collections contain 1, 2, then 3 elements, repeating, each drawn
+///   from the element type's default generator;
+/// - tuples and arrays draw each element from the element type's
+///   default generator.
+///
+/// Example
+/// ```rust
+/// use boulder::{Generatable, Generator};
+///
+/// let mut g = <(u8, String)>::generator();
+/// assert_eq!(g.generate(), (0, "string-0".to_string()));
+/// assert_eq!(g.generate(), (1, "string-1".to_string()));
+///
+/// let mut g = Vec::<i64>::generator();
+/// assert_eq!(g.generate(), vec![0]);
+/// assert_eq!(g.generate(), vec![1, 2]);
+///
+/// let mut g = <[char; 3]>::generator();
+/// assert_eq!(g.generate(), ['a', 'b', 'c']);
+/// ```
+pub struct BuiltinGenerator<V, T> {
+    inner: Box<dyn Generator<Output = V>>,
+    _result: PhantomData<T>,
+}
+
+impl<V, T> BuiltinGenerator<V, T> {
+    fn new<G>(inner: G) -> Self
+    where
+        G: Generator<Output = V>,
+    {
+        Self {
+            inner: Box::new(inner),
+            _result: PhantomData,
+        }
+    }
+}
+
+impl<V, T> Generator for BuiltinGenerator<V, T>
+where
+    V: 'static,
+    T: FromBuiltin<V> + 'static,
+{
+    type Output = T;
+    fn generate(&mut self) -> T {
+        T::from_builtin(self.inner.generate())
+    }
+}
+
+/// Conversion from a built-in type to itself, or to a wrapper around
+/// it.
+///
+/// This is public only so that it can appear in bounds; it can't be
+/// named outside this crate.
+pub trait FromBuiltin<V> {
+    fn from_builtin(value: V) -> Self;
+}
+
+impl<V, W> FromBuiltin<V> for W
+where
+    W: BoulderWrapper,
+    W::Inner: FromBuiltin<V>,
+{
+    fn from_builtin(value: V) -> Self {
+        W::wrap(W::Inner::from_builtin(value))
+    }
+}
+
+// The base cases are implemented type by type, rather than once for
+// all `V`, so that they can't overlap with the wrapper case above.
+macro_rules! builtin {
+    ($([$($params:tt)*] $ty:ty, [$($build_wc:tt)*] $build:expr, [$($gen_wc:tt)*] $gen:expr;)*) => {
+        $(
+            impl<$($params)*> BuildableBase for $ty where $($build_wc)* {
+                type Builder<T> = BuiltinBuilder<$ty, T>;
+                fn builder_for<T>() -> Self::Builder<T> {
+                    BuiltinBuilder::new($build)
+                }
+            }
+
+            impl<$($params)*> FromBuiltin<$ty> for $ty {
+                fn from_builtin(value: $ty) -> Self {
+                    value
+                }
+            }
+
+            impl<$($params)*> GeneratableBase for $ty where $($gen_wc)* {
+                type Generator<T> = BuiltinGenerator<$ty, T>;
+                fn generator_for<T>() -> Self::Generator<T> {
+                    BuiltinGenerator::new($gen)
+                }
+            }
+        )*
+    };
+}
+
+fn count<T, U, F>(
+    mut from: T,
+    mut next: F,
+    mut map: impl FnMut(&T) -> U + 'static,
+) -> impl Generator<Output = U>
+where
+    T: 'static,
+    U: 'static,
+    F: FnMut(&T) -> T + 'static,
+{
+    move || {
+        let res = map(&from);
+        from = next(&from);
+        res
+    }
+}
+
+macro_rules! integers {
+    ($($ty:ty),*) => {
+        builtin! {
+            $(
+                [] $ty, [] 0, [] count(0 as $ty, |n| n.wrapping_add(1), |n| *n);
+            )*
+        }
+    };
+}
+
+integers!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+fn sizes() -> impl Generator<Output = usize> {
+    Cycle::new(1usize..=3)
+}
+
+fn epoch() -> chrono::NaiveDateTime {
+    chrono::DateTime::UNIX_EPOCH.naive_utc()
+}
+
+builtin! {
+    [] f32, [] 0.0, [] count(0.0f32, |n| n + 1.0, |n| *n);
+    [] f64, [] 0.0, [] count(0.0f64, |n| n + 1.0, |n| *n);
+    [] bool, [] false, [] Cycle::new([false, true].into_iter());
+    [] char, [] char::default(), [] Cycle::new('a'..='z');
+    [] String, [] String::new(), [] count(0usize, |n| n + 1, |n| format!("string-{}", n));
+
+    [] chrono::NaiveDateTime, [] epoch(), [] count(epoch(), |t| *t + chrono::Duration::days(1), |t| *t);
+    [] chrono::NaiveDate, [] epoch().date(), [] count(epoch().date(), |t| *t + chrono::Duration::days(1), |t| *t);
+    [] chrono::NaiveTime, [] epoch().time(), [] count(epoch().time(), |t| *t + chrono::Duration::minutes(1), |t| *t);
+    [] chrono::DateTime<chrono::Utc>, [] epoch().and_utc(),
+        [] crate::Time::new(epoch().and_utc(), chrono::Duration::days(1));
+    [] chrono::DateTime<chrono::FixedOffset>, [] epoch().and_utc().fixed_offset(),
+        [] crate::Time::new(epoch().and_utc().fixed_offset(), chrono::Duration::days(1));
+
+    [X] Vec<X>, [] Vec::new(),
+        [X: Generatable + 'static] Sample::new(X::generator(), sizes());
+    [X] VecDeque<X>, [] VecDeque::new(),
+        [X: Generatable + 'static] Sample::new(X::generator(), sizes());
+    [X] HashSet<X>, [] HashSet::new(),
+        [X: Generatable + Eq + Hash + 'static] Sample::new(X::generator(), sizes());
+    [X] BTreeSet<X>, [] BTreeSet::new(),
+        [X: Generatable + Ord + 'static] Sample::new(X::generator(), sizes());
+    [K, X] HashMap<K, X>, [] HashMap::new(),
+        [K: Generatable + Eq + Hash + 'static, X: Generatable + 'static]
+        Sample::new(<(K, X)>::generator(), sizes());
+    [K, X] BTreeMap<K, X>, [] BTreeMap::new(),
+        [K: Generatable + Ord + 'static, X: Generatable + 'static]
+        Sample::new(<(K, X)>::generator(), sizes());
+
+    [X, const N: usize] [X; N], [X: Buildable] std::array::from_fn(|_| X::builder().build()),
+        [X: Generatable + 'static] {
+            let mut gen = X::generator();
+            move || std::array::from_fn(|_| gen.generate())
+        };
+}
+
+macro_rules! tuples {
+    ($(($($name:ident),*);)*) => {
+        builtin! {
+            $(
+                [$($name),*] ($($name,)*),
+                [$($name: Buildable),*] ($($name::builder().build(),)*),
+                [$($name: Generatable + 'static),*] {
+                    #[allow(non_snake_case)]
+                    let ($(mut $name,)*) = ($($name::generator(),)*);
+                    move || ($($name.generate(),)*)
+                };
+            )*
+        }
+    };
+}
+
+tuples! {
+    (A);
+    (A, B);
+    (A, B, C);
+    (A, B, C, D);
+    (A, B, C, D, E);
+    (A, B, C, D, E, F);
+    (A, B, C, D, E, F, G);
+    (A, B, C, D, E, F, G, H);
+}
+
+/// Collect a sequence of exactly `N` items into an array.
+///
+/// The derive macros use this for `sequence` fields of array type,
+/// since arrays do not implement [`FromIterator`].
+#[doc(hidden)]
+pub fn collect_array<T, I, const N: usize>(iter: I) -> [T; N]
+where
+    I: IntoIterator<Item = T>,
+{
+    let items = iter.into_iter().collect::<Vec<_>>();
+    let len = items.len();
+    items.try_into().unwrap_or_else(|_| {
+        panic!(
+            "sequence of {} items cannot fill an array of length {}",
+            len, N
+        )
+    })
+}
@@ -1,11 +1,21 @@
 //! Standard generator implementations.
 
+use super::rng::Rng;
 use crate::Generator;
 
 pub use boulder_derive::repeat as Repeat;
 pub use boulder_derive::string_pattern as Pattern;
+pub use boulder_derive::weighted as Weighted;
 
 use num::One;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
+
+/// The number of consecutive duplicates tolerated by [`Unique`],
+/// [`HashSetOf`] and [`BTreeMapOf`] before giving up.
+pub const DEFAULT_RETRIES: usize = 100;
 
 /// The same value every time.
 ///
@@ -138,6 +148,11 @@ where
 /// Produce collections from a pair of generators, one for the values
 /// themselves, one for the size of yielded collection.
 ///
+/// Note that the size is the number of values drawn, not the size of
+/// the resulting collection. When collecting into a set or map,
+/// duplicate values collapse and the result may be smaller; use
+/// [`HashSetOf`] or [`BTreeMapOf`] if the exact size matters.
+///
 /// Example:
 /// ```rust
 /// use boulder::{Cycle, Generator, Repeat, Sample};
@@ -367,3 +382,1495 @@ impl<T: Clone + 'static> Generator for Repeat<T> {
         res
     }
 }
+
+/// Strings which tend to break naive code.
+///
+/// This cycles through a fixed collection of awkward values: the
+/// empty string, whitespace of various kinds, a very long string,
+/// combining characters, right-to-left text, emoji built from
+/// zero-width-joiner sequences, embedded NUL bytes, SQL and HTML
+/// metacharacters, strings which are not in Unicode normal form C,
+/// and escaped lone surrogates (which cannot occur in a Rust
+/// [`String`] directly, but often arrive as escapes in JSON). The
+/// collection is available via [`EdgeStrings::values`].
+///
+/// To only occasionally produce these values in an otherwise well
+/// behaved sequence, see [`WithEdgeStrings`].
+///
+/// Example:
+/// ```rust
+/// use boulder::{EdgeStrings, Generator};
+///
+/// let mut g = EdgeStrings::new();
+/// assert_eq!(g.generate(), "");
+/// assert!(g.generate().trim().is_empty());
+/// let values = EdgeStrings::values();
+/// for _ in 2..values.len() {
+///     g.generate();
+/// }
+/// assert_eq!(g.generate(), "");
+/// ```
+#[derive(Clone)]
+pub struct EdgeStrings {
+    pub(crate) base: Vec<String>,
+    pub(crate) index: usize,
+}
+
+impl EdgeStrings {
+    /// Create a new generator.
+    pub fn new() -> Self {
+        Self {
+            base: Self::values(),
+            index: 0,
+        }
+    }
+
+    /// The values yielded, in the order they are yielded.
+    pub fn values() -> Vec<String> {
+        vec![
+            String::new(),
+            " ".to_string(),
+            " \t\r\n ".to_string(),
+            "\u{a0}\u{2003}\u{3000}".to_string(),
+            "\u{feff}\u{200b}".to_string(),
+            "x".repeat(65536),
+            "Z\u{351}\u{36b}\u{343}\u{36a}\u{302}a\u{300}\u{301}\u{302}\u{303}\u{304}".to_string(),
+            "\u{5e9}\u{5dc}\u{5d5}\u{5dd}".to_string(),
+            "\u{627}\u{644}\u{633}\u{644}\u{627}\u{645} abc".to_string(),
+            "\u{202e}txt.exe".to_string(),
+            "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}".to_string(),
+            "\u{1f3f3}\u{fe0f}\u{200d}\u{1f308}\u{1f1ec}\u{1f1e7}".to_string(),
+            "\0".to_string(),
+            "before\0after".to_string(),
+            "'; DROP TABLE users; --".to_string(),
+            "' OR '1'='1".to_string(),
+            "<script>alert(1)</script>".to_string(),
+            "&amp; &lt; \" ' < > &".to_string(),
+            "e\u{301}".to_string(),
+            "\u{212b}\u{fb01}".to_string(),
+            "\\ud800".to_string(),
+            "\\udfff\\ud800".to_string(),
+            "%s%n{}{0}$1\\".to_string(),
+        ]
+    }
+}
+
+impl Default for EdgeStrings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator for EdgeStrings {
+    type Output = String;
+    fn generate(&mut self) -> Self::Output {
+        let res = self.base[self.index].clone();
+        self.index = (self.index + 1) % self.base.len();
+        res
+    }
+}
+
+/// Occasionally replace values with [`EdgeStrings`].
+///
+/// This wraps another generator of strings, and replaces every
+/// `every`th value it would have produced with the next value from
+/// [`EdgeStrings`]. The underlying generator is not advanced for
+/// replaced values, so the remaining values are unchanged in order.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Inc, Pattern, WithEdgeStrings};
+///
+/// let mut g = WithEdgeStrings::new(Pattern!("user-{}", Inc(1)), 3);
+/// assert_eq!(g.generate(), "user-1");
+/// assert_eq!(g.generate(), "user-2");
+/// assert_eq!(g.generate(), "");
+/// assert_eq!(g.generate(), "user-3");
+/// assert_eq!(g.generate(), "user-4");
+/// assert_eq!(g.generate(), " ");
+/// ```
+#[derive(Clone)]
+pub struct WithEdgeStrings<T> {
+    pub(crate) inner: T,
+    pub(crate) edges: EdgeStrings,
+    pub(crate) every: usize,
+    pub(crate) index: usize,
+}
+
+impl<T> WithEdgeStrings<T> {
+    /// Create a new generator.
+    ///
+    /// Every `every`th value is drawn from [`EdgeStrings`], and the
+    /// rest from `inner`. An `every` of 1 therefore behaves exactly
+    /// like [`EdgeStrings`].
+    ///
+    /// This will panic if `every` is zero.
+    pub fn new(inner: T, every: usize) -> Self {
+        assert!(every > 0, "WithEdgeStrings requires a non-zero rate");
+        Self {
+            inner,
+            edges: EdgeStrings::new(),
+            every,
+            index: 0,
+        }
+    }
+}
+
+impl<T> Generator for WithEdgeStrings<T>
+where
+    T: Generator<Output = String>,
+{
+    type Output = String;
+    fn generate(&mut self) -> Self::Output {
+        self.index = (self.index + 1) % self.every;
+        if self.index == 0 {
+            self.edges.generate()
+        } else {
+            self.inner.generate()
+        }
+    }
+}
+
+/// Never yield the same value twice.
+///
+/// This wraps another generator, and remembers every value it has
+/// yielded. If the underlying generator produces a value that has
+/// been seen before, it is asked again, up to a bounded number of
+/// retries ([`DEFAULT_RETRIES`] unless changed with
+/// [`retries`](Unique::retries)). If no new value is found, this
+/// panics, naming the output type, since a test relying on unique
+/// values cannot proceed.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Repeat, Unique};
+///
+/// let mut g = Unique::new(Repeat::new([1, 1, 2, 3, 2, 4]));
+/// assert_eq!(g.generate(), 1);
+/// assert_eq!(g.generate(), 2);
+/// assert_eq!(g.generate(), 3);
+/// assert_eq!(g.generate(), 4);
+/// ```
+///
+/// Exhausting the underlying sequence panics:
+/// ```rust,should_panic
+/// use boulder::{Generator, Repeat, Unique};
+///
+/// let mut g = Unique::new(Repeat::new([1, 2])).retries(5);
+/// g.generate();
+/// g.generate();
+/// g.generate();
+/// ```
+pub struct Unique<T, U> {
+    pub(crate) inner: T,
+    pub(crate) seen: HashSet<U>,
+    pub(crate) retries: usize,
+}
+
+impl<T, U> Unique<T, U> {
+    /// Create a new generator, wrapping `inner`.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            seen: HashSet::new(),
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Set the number of consecutive duplicates to tolerate before
+    /// panicking.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Forget all previously yielded values.
+    pub fn reset(&mut self) {
+        self.seen.clear();
+    }
+}
+
+impl<T, U> Unique<T, U>
+where
+    U: Hash + Eq + Clone,
+{
+    pub(crate) fn accept(&mut self, value: U) -> Option<U> {
+        if self.seen.insert(value.clone()) {
+            Option::Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn exhausted<U>(what: &str, retries: usize) -> ! {
+    panic!(
+        "{} failed to find a new value of type {} after {} retries",
+        what,
+        std::any::type_name::<U>(),
+        retries
+    )
+}
+
+impl<T, U> Generator for Unique<T, U>
+where
+    T: Generator<Output = U>,
+    U: Hash + Eq + Clone + 'static,
+{
+    type Output = U;
+    fn generate(&mut self) -> Self::Output {
+        for _ in 0..=self.retries {
+            let value = self.inner.generate();
+            if let Option::Some(value) = self.accept(value) {
+                return value;
+            }
+        }
+        exhausted::<U>("Unique", self.retries)
+    }
+}
+
+/// Sets of an exact size.
+///
+/// Produce [`HashSet`] instances from a pair of generators, one for
+/// the values themselves, one for the size of each yielded set. Unlike
+/// collecting a [`Sample`] into a set, duplicate values are discarded
+/// and replaced, so each set has exactly the requested number of
+/// elements. As with [`Unique`], this panics if too many consecutive
+/// duplicates are produced.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, HashSetOf, Inc, Repeat};
+/// use std::collections::HashSet;
+///
+/// let mut g = HashSetOf::new(Repeat::new([1, 1, 2, 2, 3]), Inc(1usize));
+/// assert_eq!(g.generate(), HashSet::from([1]));
+/// assert_eq!(g.generate(), HashSet::from([1, 2]));
+/// assert_eq!(g.generate(), HashSet::from([1, 2, 3]));
+/// ```
+pub struct HashSetOf<T, U> {
+    pub(crate) value: T,
+    pub(crate) count: U,
+    pub(crate) retries: usize,
+}
+
+impl<T, U> HashSetOf<T, U> {
+    /// Create a new generator.
+    ///
+    /// Each yielded set will contain exactly as many elements as the
+    /// next value of `count`, drawn from `value`.
+    pub fn new(value: T, count: U) -> Self {
+        Self {
+            value,
+            count,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Set the number of consecutive duplicates to tolerate before
+    /// panicking.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+impl<T, U, X> Generator for HashSetOf<T, U>
+where
+    T: Generator<Output = X>,
+    U: Generator<Output = usize>,
+    X: Hash + Eq + 'static,
+{
+    type Output = HashSet<X>;
+    fn generate(&mut self) -> Self::Output {
+        let count = self.count.generate();
+        let mut res = HashSet::with_capacity(count);
+        let mut failures = 0;
+        while res.len() < count {
+            if res.insert(self.value.generate()) {
+                failures = 0;
+            } else if failures == self.retries {
+                exhausted::<X>("HashSetOf", self.retries)
+            } else {
+                failures += 1;
+            }
+        }
+        res
+    }
+}
+
+/// Maps of an exact size.
+///
+/// Produce [`BTreeMap`] instances from three generators, one for the
+/// keys, one for the values and one for the size of each yielded
+/// map. Duplicate keys are discarded and replaced, so each map has
+/// exactly the requested number of entries; the value generator is
+/// only advanced for keys which are kept. As with [`Unique`], this
+/// panics if too many consecutive duplicates are produced.
+///
+/// Example:
+/// ```rust
+/// use boulder::{BTreeMapOf, Generator, Inc, Pattern, Repeat};
+/// use std::collections::BTreeMap;
+///
+/// let mut g = BTreeMapOf::new(Repeat::new([1, 1, 2]), Pattern!("v{}", Inc(0)), Repeat::new([2usize]));
+/// assert_eq!(
+///     g.generate(),
+///     BTreeMap::from([(1, "v0".to_string()), (2, "v1".to_string())])
+/// );
+/// ```
+pub struct BTreeMapOf<K, V, U> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    pub(crate) count: U,
+    pub(crate) retries: usize,
+}
+
+impl<K, V, U> BTreeMapOf<K, V, U> {
+    /// Create a new generator.
+    ///
+    /// Each yielded map will contain exactly as many entries as the
+    /// next value of `count`, with keys drawn from `key` and values
+    /// from `value`.
+    pub fn new(key: K, value: V, count: U) -> Self {
+        Self {
+            key,
+            value,
+            count,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Set the number of consecutive duplicate keys to tolerate before
+    /// panicking.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+impl<K, V, U, X, Y> Generator for BTreeMapOf<K, V, U>
+where
+    K: Generator<Output = X>,
+    V: Generator<Output = Y>,
+    U: Generator<Output = usize>,
+    X: Ord + 'static,
+    Y: 'static,
+{
+    type Output = BTreeMap<X, Y>;
+    fn generate(&mut self) -> Self::Output {
+        let count = self.count.generate();
+        let mut res = BTreeMap::new();
+        let mut failures = 0;
+        while res.len() < count {
+            let key = self.key.generate();
+            if let std::collections::btree_map::Entry::Vacant(e) = res.entry(key) {
+                e.insert(self.value.generate());
+                failures = 0;
+            } else if failures == self.retries {
+                exhausted::<X>("BTreeMapOf", self.retries)
+            } else {
+                failures += 1;
+            }
+        }
+        res
+    }
+}
+
+/// Choose values with given relative frequencies.
+///
+/// Each value is given an integer weight. Over every window of
+/// consecutive outputs whose length is the sum of the weights, each
+/// value occurs exactly as many times as its weight, and occurrences
+/// are spread as evenly as possible across the window rather than
+/// bunched together.
+///
+/// If you instead want independent choices with the same relative
+/// frequencies, use [`seeded`](Weighted::seeded); the sequence is
+/// still reproducible for a given seed.
+///
+/// The [`Weighted!`](macro@Weighted) macro is usually the most
+/// convenient way to construct one of these.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Weighted};
+///
+/// let mut g = Weighted::new([("active", 3), ("suspended", 1)]);
+/// assert_eq!(g.generate(), "active");
+/// assert_eq!(g.generate(), "active");
+/// assert_eq!(g.generate(), "suspended");
+/// assert_eq!(g.generate(), "active");
+/// assert_eq!(g.generate(), "active");
+/// ```
+#[derive(Clone)]
+pub struct Weighted<T: Clone> {
+    pub(crate) base: Vec<(T, u64)>,
+    pub(crate) current: Vec<i128>,
+    pub(crate) total: u64,
+    pub(crate) rng: Option<Rng>,
+}
+
+impl<T: Clone> Weighted<T> {
+    /// Create a new generator from `(value, weight)` pairs.
+    ///
+    /// This will panic if the weights sum to zero.
+    pub fn new<X: IntoIterator<Item = (T, u64)>>(base: X) -> Self {
+        let base: Vec<_> = base.into_iter().collect();
+        let total = base.iter().map(|(_, w)| w).sum();
+        assert!(total > 0, "Weighted requires at least one non-zero weight");
+        Self {
+            current: vec![0; base.len()],
+            base,
+            total,
+            rng: None,
+        }
+    }
+
+    /// Choose each value independently at random, according to its
+    /// weight, using a generator seeded with `seed`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use boulder::{Generator, Weighted};
+    ///
+    /// let mut g1 = Weighted!("a" => 9, "b" => 1).seeded(7);
+    /// let mut g2 = Weighted!("a" => 9, "b" => 1).seeded(7);
+    /// let v1: Vec<String> = (0..100).map(|_| g1.generate()).collect();
+    /// let v2: Vec<String> = (0..100).map(|_| g2.generate()).collect();
+    /// assert_eq!(v1, v2);
+    /// assert!(v1.iter().filter(|v| *v == "a").count() > 70);
+    /// ```
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.rng = Option::Some(Rng::new(seed));
+        self
+    }
+
+    pub(crate) fn next_index(&mut self) -> usize {
+        if let Option::Some(rng) = &mut self.rng {
+            let mut target = rng.below(self.total);
+            for (i, (_, weight)) in self.base.iter().enumerate() {
+                if target < *weight {
+                    return i;
+                }
+                target -= weight;
+            }
+            unreachable!()
+        } else {
+            // Smooth weighted round robin: every value accrues its
+            // weight each step, and the richest pays the total.
+            let mut best = 0;
+            for (i, (_, weight)) in self.base.iter().enumerate() {
+                self.current[i] += *weight as i128;
+                if self.current[i] > self.current[best] {
+                    best = i;
+                }
+            }
+            self.current[best] -= self.total as i128;
+            best
+        }
+    }
+}
+
+impl<T: Clone + 'static> Generator for Weighted<T> {
+    type Output = T;
+    fn generate(&mut self) -> Self::Output {
+        let index = self.next_index();
+        self.base[index].0.clone()
+    }
+}
+
+/// How often an occasional event happens.
+///
+/// This is used by [`Maybe`] and [`OkOrErr`] to decide which outputs
+/// take the less common path. The first two forms are deterministic,
+/// and spread the events evenly; the last is random, but reproducible
+/// for a given seed.
+#[derive(Clone, Debug)]
+pub enum Frequency {
+    /// Every `n`th output, starting with output `n`.
+    Every(u64),
+    /// `n` out of every `d` outputs, as evenly spaced as possible.
+    Ratio(u64, u64),
+    /// Each output independently with probability `p`, using a
+    /// generator seeded with `seed`.
+    Seeded { p: f64, seed: u64 },
+}
+
+#[derive(Clone)]
+pub(crate) struct Schedule {
+    numerator: u64,
+    denominator: u64,
+    index: u64,
+    rng: Option<(f64, Rng)>,
+}
+
+impl Schedule {
+    pub(crate) fn new(frequency: Frequency) -> Self {
+        let (numerator, denominator, rng) = match frequency {
+            Frequency::Every(n) => (1, n, None),
+            Frequency::Ratio(n, d) => (n, d, None),
+            Frequency::Seeded { p, seed } => {
+                assert!(
+                    (0.0..=1.0).contains(&p),
+                    "Frequency::Seeded requires a probability between 0 and 1"
+                );
+                (0, 1, Option::Some((p, Rng::new(seed))))
+            }
+        };
+        assert!(denominator > 0, "Frequency requires a non-zero period");
+        assert!(
+            numerator <= denominator,
+            "Frequency::Ratio cannot exceed one"
+        );
+        Self {
+            numerator,
+            denominator,
+            index: 0,
+            rng,
+        }
+    }
+
+    /// Whether the next output is an event.
+    pub(crate) fn next(&mut self) -> bool {
+        if let Option::Some((p, rng)) = &mut self.rng {
+            rng.unit() < *p
+        } else {
+            let i = self.index;
+            self.index = (self.index + 1) % self.denominator;
+            (i + 1) * self.numerator / self.denominator > i * self.numerator / self.denominator
+        }
+    }
+}
+
+/// Sometimes [`None`], otherwise values from another generator.
+///
+/// This is a sparse version of [`Some`](struct@Some): it wraps a
+/// generator of `T` to produce [`Option<T>`], but yields `None` on the
+/// schedule given by a [`Frequency`]. The underlying generator is not
+/// advanced when `None` is yielded.
+///
+/// Note that the derived generators pass through [`Option<T>`] by
+/// always yielding `Some`; use this for a field to exercise the
+/// missing case too.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Frequency, Generator, Inc, Maybe};
+///
+/// let mut g = Maybe::new(Inc(1), Frequency::Every(3));
+/// assert_eq!(g.generate(), Some(1));
+/// assert_eq!(g.generate(), Some(2));
+/// assert_eq!(g.generate(), None);
+/// assert_eq!(g.generate(), Some(3));
+///
+/// let mut g = Maybe::new(Inc(1), Frequency::Ratio(2, 3));
+/// assert_eq!(g.generate(), Some(1));
+/// assert_eq!(g.generate(), None);
+/// assert_eq!(g.generate(), None);
+/// assert_eq!(g.generate(), Some(2));
+/// ```
+#[derive(Clone)]
+pub struct Maybe<T> {
+    pub(crate) inner: T,
+    pub(crate) schedule: Schedule,
+}
+
+impl<T> Maybe<T> {
+    /// Create a new generator.
+    ///
+    /// Values come from `inner`, except when `none` says a `None`
+    /// should be produced instead.
+    pub fn new(inner: T, none: Frequency) -> Self {
+        Self {
+            inner,
+            schedule: Schedule::new(none),
+        }
+    }
+}
+
+impl<T: Generator> Generator for Maybe<T> {
+    type Output = Option<<T as Generator>::Output>;
+    fn generate(&mut self) -> Self::Output {
+        if self.schedule.next() {
+            None
+        } else {
+            Option::Some(self.inner.generate())
+        }
+    }
+}
+
+/// Mostly [`Ok`], sometimes [`Err`].
+///
+/// This combines a generator of successes and a generator of
+/// failures into a generator of [`Result`] values, yielding `Err` on
+/// the schedule given by a [`Frequency`]. Each underlying generator is
+/// only advanced when its value is used.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Frequency, Generator, Inc, OkOrErr, Pattern};
+///
+/// let mut g = OkOrErr::new(Inc(1), Pattern!("error {}", Inc(1)), Frequency::Every(2));
+/// assert_eq!(g.generate(), Ok(1));
+/// assert_eq!(g.generate(), Err("error 1".to_string()));
+/// assert_eq!(g.generate(), Ok(2));
+/// assert_eq!(g.generate(), Err("error 2".to_string()));
+/// ```
+#[derive(Clone)]
+pub struct OkOrErr<T, E> {
+    pub(crate) ok: T,
+    pub(crate) err: E,
+    pub(crate) schedule: Schedule,
+}
+
+impl<T, E> OkOrErr<T, E> {
+    /// Create a new generator.
+    ///
+    /// Successes come from `ok` and failures from `err`; `errors`
+    /// determines which outputs are failures.
+    pub fn new(ok: T, err: E, errors: Frequency) -> Self {
+        Self {
+            ok,
+            err,
+            schedule: Schedule::new(errors),
+        }
+    }
+}
+
+impl<T: Generator, E: Generator> Generator for OkOrErr<T, E> {
+    type Output = Result<<T as Generator>::Output, <E as Generator>::Output>;
+    fn generate(&mut self) -> Self::Output {
+        if self.schedule.next() {
+            Err(self.err.generate())
+        } else {
+            Ok(self.ok.generate())
+        }
+    }
+}
+
+/// A clonable handle to a single underlying generator.
+///
+/// Every clone of a [`Shared`] draws from the same generator, so the
+/// values are divided between them. This is the way to correlate
+/// fields, or instances of different types, which would otherwise
+/// each own an independent generator.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generatable, Generator, Inc, Shared};
+///
+/// #[derive(Generatable)]
+/// struct User {
+///    id: i32,
+/// }
+///
+/// #[derive(Generatable)]
+/// struct Admin {
+///    id: i32,
+/// }
+///
+/// let ids = Shared::new(Inc(1));
+/// let mut users = User::generator().id(ids.clone());
+/// let mut admins = Admin::generator().id(ids.clone());
+/// assert_eq!(users.generate().id, 1);
+/// assert_eq!(admins.generate().id, 2);
+/// assert_eq!(users.generate().id, 3);
+/// ```
+///
+/// The handles share the generator through an `Rc<RefCell<_>>`, so a
+/// [`Shared`] is not `Send`, and all its clones must stay on one
+/// thread. Generating from a handle while the underlying generator is
+/// already generating, for example from a handle captured by that
+/// generator itself, panics.
+pub struct Shared<T> {
+    pub(crate) inner: Rc<RefCell<T>>,
+}
+
+impl<T> Shared<T> {
+    /// Create a new handle, taking ownership of `inner`.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(inner)),
+        }
+    }
+
+    /// Replace the underlying generator for every handle.
+    pub fn replace(&self, inner: T) -> T {
+        self.inner.replace(inner)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Generator> Generator for Shared<T> {
+    type Output = <T as Generator>::Output;
+    fn generate(&mut self) -> Self::Output {
+        self.inner.borrow_mut().generate()
+    }
+}
+
+thread_local! {
+    static SEQUENCES: RefCell<HashMap<String, u64>> = RefCell::new(HashMap::new());
+}
+
+/// A named, global, increasing sequence.
+///
+/// All instances of [`Sequence`] with the same name draw from the
+/// same counter, wherever they were created, so that (for example)
+/// every generator of orders can take its identifiers from the
+/// `"order_id"` sequence. Sequences start at 1, and can be reset
+/// with [`reset_sequence`] or [`reset_sequences`].
+///
+/// Sequences are global to the current thread. The standard test
+/// harness runs each test on its own thread, so resetting a sequence
+/// at the start of a test does not disturb tests running alongside
+/// it.
+///
+/// The output type can be any primitive integer type; this panics if
+/// the next value does not fit. The counter itself is a `u64`, which
+/// wraps around to 0 after `u64::MAX`.
+///
+/// Example:
+/// ```rust
+/// use boulder::{reset_sequence, Generator, Sequence};
+///
+/// let mut a = Sequence::<u32>::named("order_id");
+/// let mut b = Sequence::<u64>::named("order_id");
+/// let mut c = Sequence::<u32>::named("invoice_id");
+/// assert_eq!(a.generate(), 1);
+/// assert_eq!(b.generate(), 2);
+/// assert_eq!(c.generate(), 1);
+/// reset_sequence("order_id");
+/// assert_eq!(b.generate(), 1);
+/// ```
+pub struct Sequence<T> {
+    pub(crate) name: String,
+    pub(crate) _result_marker: core::marker::PhantomData<T>,
+}
+
+impl<T> Clone for Sequence<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            _result_marker: Default::default(),
+        }
+    }
+}
+
+impl<T> Sequence<T> {
+    /// Create a handle to the sequence called `name`.
+    pub fn named<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            _result_marker: Default::default(),
+        }
+    }
+}
+
+/// Restart the [`Sequence`] called `name` from 1.
+pub fn reset_sequence(name: &str) {
+    set_sequence(name, 1);
+}
+
+/// Make `next` the next value yielded by the [`Sequence`] called
+/// `name`.
+pub fn set_sequence(name: &str, next: u64) {
+    SEQUENCES.with(|s| s.borrow_mut().insert(name.to_string(), next));
+}
+
+/// Restart every [`Sequence`] from 1.
+pub fn reset_sequences() {
+    SEQUENCES.with(|s| s.borrow_mut().clear());
+}
+
+impl<T> Sequence<T>
+where
+    T: num::FromPrimitive,
+{
+    pub(crate) fn next_value(&self) -> T {
+        let value = SEQUENCES.with(|s| {
+            let mut s = s.borrow_mut();
+            let next = s.entry(self.name.clone()).or_insert(1);
+            let value = *next;
+            *next = next.wrapping_add(1);
+            value
+        });
+        T::from_u64(value).unwrap_or_else(|| {
+            panic!(
+                "Sequence {} value {} does not fit in {}",
+                self.name,
+                value,
+                std::any::type_name::<T>()
+            )
+        })
+    }
+}
+
+impl<T> Generator for Sequence<T>
+where
+    T: num::FromPrimitive + 'static,
+{
+    type Output = T;
+    fn generate(&mut self) -> Self::Output {
+        self.next_value()
+    }
+}
+
+/// Walk a state machine, yielding successive states.
+///
+/// The machine is defined by its start state and its allowed
+/// transitions. Where a state has several transitions, they are
+/// chosen in proportion to their weights, as for [`Weighted`]: by
+/// default deterministically, spread evenly over successive visits
+/// to that state, or independently at random with
+/// [`seeded`](StateMachine::seeded).
+///
+/// On reaching a state with no outgoing transitions, the walk stays
+/// there, yielding that state again. Used with `sequence_generator`
+/// on a collection field, each collection is a separate walk from
+/// the start state; to end each walk at a state with no outgoing
+/// transitions instead, use [`paths`](StateMachine::paths).
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, StateMachine};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum Status {
+///    Draft,
+///    Review,
+///    Published,
+///    Archived,
+/// }
+///
+/// let mut g = StateMachine::new(Status::Draft)
+///    .transition(Status::Draft, Status::Review)
+///    .transition(Status::Review, Status::Draft)
+///    .transition(Status::Review, Status::Published)
+///    .transition(Status::Published, Status::Archived);
+///
+/// assert_eq!(g.generate(), Status::Draft);
+/// assert_eq!(g.generate(), Status::Review);
+/// assert_eq!(g.generate(), Status::Draft);
+/// assert_eq!(g.generate(), Status::Review);
+/// assert_eq!(g.generate(), Status::Published);
+/// assert_eq!(g.generate(), Status::Archived);
+/// assert_eq!(g.generate(), Status::Archived);
+/// ```
+#[derive(Clone)]
+pub struct StateMachine<S: Clone> {
+    pub(crate) start: S,
+    pub(crate) edges: Vec<(S, Vec<(S, u64)>)>,
+    pub(crate) choices: Vec<Weighted<S>>,
+    pub(crate) seed: Option<u64>,
+    pub(crate) current: Option<S>,
+}
+
+impl<S: Clone + PartialEq> StateMachine<S> {
+    /// Create a new machine, starting in state `start`, with no
+    /// transitions.
+    pub fn new(start: S) -> Self {
+        Self {
+            start,
+            edges: Vec::new(),
+            choices: Vec::new(),
+            seed: None,
+            current: None,
+        }
+    }
+
+    /// Allow a transition from `from` to `to`, with weight 1.
+    pub fn transition(self, from: S, to: S) -> Self {
+        self.weighted_transition(from, to, 1)
+    }
+
+    /// Allow a transition from `from` to `to` with the given weight,
+    /// relative to the other transitions out of `from`.
+    ///
+    /// This will panic if `weight` is zero.
+    pub fn weighted_transition(mut self, from: S, to: S, weight: u64) -> Self {
+        assert!(
+            weight > 0,
+            "StateMachine transitions require a non-zero weight"
+        );
+        match self.edges.iter_mut().find(|(state, _)| *state == from) {
+            Option::Some((_, targets)) => targets.push((to, weight)),
+            None => self.edges.push((from, vec![(to, weight)])),
+        }
+        self.rebuild();
+        self
+    }
+
+    /// Choose each transition independently at random, according to
+    /// its weight, using generators seeded from `seed`.
+    ///
+    /// Example:
+    /// ```rust
+    /// use boulder::{Generator, StateMachine};
+    ///
+    /// let machine = StateMachine::new("open")
+    ///    .weighted_transition("open", "open", 3)
+    ///    .weighted_transition("open", "closed", 1)
+    ///    .seeded(5);
+    /// let mut g1 = machine.clone();
+    /// let mut g2 = machine;
+    /// let v1: Vec<_> = (0..50).map(|_| g1.generate()).collect();
+    /// let v2: Vec<_> = (0..50).map(|_| g2.generate()).collect();
+    /// assert_eq!(v1, v2);
+    /// ```
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.seed = Option::Some(seed);
+        self.rebuild();
+        self
+    }
+
+    /// Yield whole paths through the machine instead of single
+    /// states.
+    ///
+    /// Every path begins at the start state. The maximum length of
+    /// each path is taken from `lengths`; a path is shorter if it
+    /// reaches a state with no outgoing transitions first.
+    ///
+    /// Example:
+    /// ```rust
+    /// use boulder::{Generator, Inc, StateMachine};
+    ///
+    /// let mut g = StateMachine::new("draft")
+    ///    .transition("draft", "review")
+    ///    .transition("review", "draft")
+    ///    .transition("review", "published")
+    ///    .paths(Inc(2usize));
+    ///
+    /// assert_eq!(g.generate(), vec!["draft", "review"]);
+    /// assert_eq!(g.generate(), vec!["draft", "review", "draft"]);
+    /// assert_eq!(g.generate(), vec!["draft", "review", "published"]);
+    /// ```
+    pub fn paths<L>(self, lengths: L) -> Paths<S, L>
+    where
+        L: Generator<Output = usize>,
+    {
+        Paths {
+            machine: self,
+            lengths,
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.choices = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, (_, targets))| {
+                let choice = Weighted::new(targets.clone());
+                match self.seed {
+                    Option::Some(seed) => choice.seeded(seed.wrapping_add(i as u64)),
+                    None => choice,
+                }
+            })
+            .collect();
+    }
+
+    pub(crate) fn is_terminal(&self, state: &S) -> bool {
+        !self.edges.iter().any(|(from, _)| from == state)
+    }
+
+    pub(crate) fn next_state(&mut self) -> S {
+        let next = match &self.current {
+            None => self.start.clone(),
+            Option::Some(state) => match self.edges.iter().position(|(from, _)| from == state) {
+                Option::Some(i) => {
+                    let index = self.choices[i].next_index();
+                    self.choices[i].base[index].0.clone()
+                }
+                None => state.clone(),
+            },
+        };
+        self.current = Option::Some(next.clone());
+        next
+    }
+}
+
+impl<S: Clone + PartialEq + 'static> Generator for StateMachine<S> {
+    type Output = S;
+    fn generate(&mut self) -> Self::Output {
+        self.next_state()
+    }
+
+    fn begin_sequence(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Whole paths through a [`StateMachine`].
+///
+/// This is created by [`StateMachine::paths`].
+#[derive(Clone)]
+pub struct Paths<S: Clone, L> {
+    pub(crate) machine: StateMachine<S>,
+    pub(crate) lengths: L,
+}
+
+impl<S: Clone + PartialEq, L> Paths<S, L> {
+    pub(crate) fn next_path(&mut self, length: usize) -> Vec<S> {
+        self.machine.current = None;
+        let mut res = Vec::with_capacity(length);
+        for _ in 0..length {
+            let state = self.machine.next_state();
+            let terminal = self.machine.is_terminal(&state);
+            res.push(state);
+            if terminal {
+                break;
+            }
+        }
+        res
+    }
+}
+
+impl<S, L> Generator for Paths<S, L>
+where
+    S: Clone + PartialEq + 'static,
+    L: Generator<Output = usize>,
+{
+    type Output = Vec<S>;
+    fn generate(&mut self) -> Self::Output {
+        let length = self.lengths.generate();
+        self.next_path(length)
+    }
+}
+
+/// Pairs of values from two generators.
+///
+/// This is most useful for building time series samples, by pairing
+/// [`Time`] with one of the numeric series generators.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Sample, Time, Trend, Zip};
+/// use chrono::{DateTime, Duration, FixedOffset};
+///
+/// let start = DateTime::parse_from_rfc3339("2022-03-31T00:00:00+00:00").unwrap();
+/// let mut g = Sample::new(
+///     Zip::new(Time::new(start, Duration::minutes(5)), Trend::new(10.0, 0.5)),
+///     boulder::Const(3usize),
+/// );
+/// let samples: Vec<(DateTime<FixedOffset>, f64)> = g.generate();
+/// assert_eq!(samples[0], (start, 10.0));
+/// assert_eq!(samples[2], (start + Duration::minutes(10), 11.0));
+/// ```
+#[derive(Clone)]
+pub struct Zip<T, U> {
+    pub(crate) first: T,
+    pub(crate) second: U,
+}
+
+impl<T, U> Zip<T, U> {
+    /// Create a new generator pairing each output of `first` with
+    /// the corresponding output of `second`.
+    pub fn new(first: T, second: U) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T: Generator, U: Generator> Generator for Zip<T, U> {
+    type Output = (<T as Generator>::Output, <U as Generator>::Output);
+    fn generate(&mut self) -> Self::Output {
+        (self.first.generate(), self.second.generate())
+    }
+}
+
+/// A random walk.
+///
+/// The first output is the starting value; each subsequent output
+/// differs from the previous one by an amount drawn uniformly from
+/// `-step..step`. The walk is random, but reproducible for a given
+/// seed. If [`bounds`](RandomWalk::bounds) are given, the walk
+/// reflects off them and never leaves the range.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, RandomWalk};
+///
+/// let mut g = RandomWalk::new(50.0, 5.0, 17).bounds(0.0, 100.0);
+/// let v: Vec<f64> = (0..1000).map(|_| g.generate()).collect();
+/// assert_eq!(v[0], 50.0);
+/// assert!(v.windows(2).all(|w| (w[1] - w[0]).abs() <= 5.0));
+/// assert!(v.iter().all(|x| (0.0..=100.0).contains(x)));
+/// ```
+#[derive(Clone)]
+pub struct RandomWalk {
+    pub(crate) value: f64,
+    pub(crate) step: f64,
+    pub(crate) bounds: Option<(f64, f64)>,
+    pub(crate) rng: Rng,
+    pub(crate) started: bool,
+}
+
+impl RandomWalk {
+    /// Create a new walk starting at `start`, moving by at most
+    /// `step` each time, using a generator seeded with `seed`.
+    pub fn new(start: f64, step: f64, seed: u64) -> Self {
+        assert!(step >= 0.0, "RandomWalk requires a non-negative step");
+        Self {
+            value: start,
+            step,
+            bounds: None,
+            rng: Rng::new(seed),
+            started: false,
+        }
+    }
+
+    /// Keep the walk between `min` and `max` inclusive. A starting
+    /// value outside the range is moved to the nearest end of it.
+    pub fn bounds(mut self, min: f64, max: f64) -> Self {
+        assert!(min <= max, "RandomWalk requires min <= max");
+        self.value = self.value.clamp(min, max);
+        self.bounds = Option::Some((min, max));
+        self
+    }
+
+    pub(crate) fn next_value(&mut self) -> f64 {
+        if self.started {
+            self.value += (self.rng.unit() * 2.0 - 1.0) * self.step;
+            if let Option::Some((min, max)) = self.bounds {
+                if self.value > max {
+                    self.value = 2.0 * max - self.value;
+                }
+                if self.value < min {
+                    self.value = 2.0 * min - self.value;
+                }
+                // A step larger than the range can overshoot twice.
+                self.value = self.value.clamp(min, max);
+            }
+        }
+        self.started = true;
+        self.value
+    }
+}
+
+impl Generator for RandomWalk {
+    type Output = f64;
+    fn generate(&mut self) -> Self::Output {
+        self.next_value()
+    }
+}
+
+/// A seasonal pattern.
+///
+/// The output follows a sine wave around `mean`, which repeats every
+/// `period` outputs.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Sine};
+///
+/// let mut g = Sine::new(20.0, 5.0, 4.0);
+/// let v: Vec<f64> = (0..5).map(|_| g.generate()).collect();
+/// let expected = [20.0, 25.0, 20.0, 15.0, 20.0];
+/// assert!(v.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9));
+/// ```
+#[derive(Clone)]
+pub struct Sine {
+    pub(crate) mean: f64,
+    pub(crate) amplitude: f64,
+    pub(crate) period: f64,
+    pub(crate) phase: f64,
+    pub(crate) index: u64,
+}
+
+impl Sine {
+    /// Create a new wave with the given `mean` and `amplitude`,
+    /// repeating every `period` outputs.
+    pub fn new(mean: f64, amplitude: f64, period: f64) -> Self {
+        assert!(period > 0.0, "Sine requires a positive period");
+        Self {
+            mean,
+            amplitude,
+            period,
+            phase: 0.0,
+            index: 0,
+        }
+    }
+
+    /// Start the wave `phase` outputs into its cycle.
+    pub fn phase(mut self, phase: f64) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    pub(crate) fn next_value(&mut self) -> f64 {
+        let angle = 2.0 * std::f64::consts::PI * (self.index as f64 + self.phase) / self.period;
+        self.index += 1;
+        self.mean + self.amplitude * angle.sin()
+    }
+}
+
+impl Generator for Sine {
+    type Output = f64;
+    fn generate(&mut self) -> Self::Output {
+        self.next_value()
+    }
+}
+
+/// A linear trend, optionally with noise.
+///
+/// Output `i` is `start + i * slope`, plus, if
+/// [`noise`](Trend::noise) is given, an amount drawn uniformly from
+/// `-amplitude..amplitude`.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generator, Trend};
+///
+/// let mut g = Trend::new(100.0, -2.0).noise(0.5, 3);
+/// let v: Vec<f64> = (0..50).map(|_| g.generate()).collect();
+/// assert!(v
+///     .iter()
+///     .enumerate()
+///     .all(|(i, x)| (x - (100.0 - 2.0 * i as f64)).abs() <= 0.5));
+/// ```
+#[derive(Clone)]
+pub struct Trend {
+    pub(crate) start: f64,
+    pub(crate) slope: f64,
+    pub(crate) noise: Option<(f64, Rng)>,
+    pub(crate) index: u64,
+}
+
+impl Trend {
+    /// Create a new trend, starting at `start` and changing by
+    /// `slope` each output.
+    pub fn new(start: f64, slope: f64) -> Self {
+        Self {
+            start,
+            slope,
+            noise: None,
+            index: 0,
+        }
+    }
+
+    /// Add uniform noise of at most `amplitude` to each output,
+    /// using a generator seeded with `seed`.
+    pub fn noise(mut self, amplitude: f64, seed: u64) -> Self {
+        self.noise = Option::Some((amplitude, Rng::new(seed)));
+        self
+    }
+
+    pub(crate) fn next_value(&mut self) -> f64 {
+        let mut res = self.start + self.index as f64 * self.slope;
+        if let Option::Some((amplitude, rng)) = &mut self.noise {
+            res += (rng.unit() * 2.0 - 1.0) * *amplitude;
+        }
+        self.index += 1;
+        res
+    }
+}
+
+impl Generator for Trend {
+    type Output = f64;
+    fn generate(&mut self) -> Self::Output {
+        self.next_value()
+    }
+}
+
+/// Step changes and spikes in a numeric series.
+///
+/// This wraps another generator of `f64`, and adds offsets to its
+/// outputs at given indices (counting from 0). A step change
+/// persists for all later outputs; a spike affects only one.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Const, Generator, WithEvents};
+///
+/// let mut g = WithEvents::new(Const(1.0))
+///     .step_at(2, 10.0)
+///     .spike_at(4, 100.0);
+/// let v: Vec<f64> = (0..6).map(|_| g.generate()).collect();
+/// assert_eq!(v, vec![1.0, 1.0, 11.0, 11.0, 111.0, 11.0]);
+/// ```
+#[derive(Clone)]
+pub struct WithEvents<T> {
+    pub(crate) inner: T,
+    pub(crate) steps: Vec<(u64, f64)>,
+    pub(crate) spikes: Vec<(u64, f64)>,
+    pub(crate) index: u64,
+}
+
+impl<T> WithEvents<T> {
+    /// Create a new wrapper around `inner`, with no events.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            steps: Vec::new(),
+            spikes: Vec::new(),
+            index: 0,
+        }
+    }
+
+    /// Add `delta` to output `index` and every output after it.
+    pub fn step_at(mut self, index: u64, delta: f64) -> Self {
+        self.steps.push((index, delta));
+        self
+    }
+
+    /// Add `delta` to output `index` only.
+    pub fn spike_at(mut self, index: u64, delta: f64) -> Self {
+        self.spikes.push((index, delta));
+        self
+    }
+
+    pub(crate) fn next_offset(&mut self) -> f64 {
+        let index = self.index;
+        self.index += 1;
+        let steps: f64 = self
+            .steps
+            .iter()
+            .filter(|(i, _)| *i <= index)
+            .map(|(_, delta)| delta)
+            .sum();
+        let spikes: f64 = self
+            .spikes
+            .iter()
+            .filter(|(i, _)| *i == index)
+            .map(|(_, delta)| delta)
+            .sum();
+        steps + spikes
+    }
+}
+
+impl<T: Generator<Output = f64>> Generator for WithEvents<T> {
+    type Output = f64;
+    fn generate(&mut self) -> Self::Output {
+        self.inner.generate() + self.next_offset()
+    }
+}
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+pub(crate) struct DepthGuard(usize);
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(self.0));
+    }
+}
+
+/// Collections of recursively generated values, with a depth limit.
+///
+/// A type like `struct Node { children: Vec<Box<Node>> }` cannot be
+/// generated naively, because generating a node requires generating
+/// nodes. This generator tracks how deeply nested the current
+/// generation is, and shrinks the number of elements it yields as
+/// that grows: at depth `d` it yields `n * (max_depth - d) /
+/// max_depth` elements (rounding down), where `n` comes from the
+/// `branching` generator, and it yields none at all once `max_depth`
+/// is reached.
+///
+/// The element generator is only created the first time it is
+/// needed, by calling `make`, so that creating a generator for a
+/// recursive type does not itself recurse.
+///
+/// This is usually created by the `recursive` attribute of
+/// [`Generatable`](macro@crate::Generatable), rather than directly.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Const, Generatable, Generator};
+///
+/// #[derive(Generatable)]
+/// struct Node {
+///    #[boulder(recursive(max_depth = 3, branching = Const(3usize)))]
+///    children: Vec<Box<Node>>,
+/// }
+///
+/// fn height(n: &Node) -> usize {
+///    n.children.iter().map(|c| 1 + height(c)).max().unwrap_or(0)
+/// }
+///
+/// let n = Node::generator().generate();
+/// assert_eq!(n.children.len(), 3);
+/// assert_eq!(n.children[0].children.len(), 2);
+/// assert_eq!(n.children[0].children[0].children.len(), 1);
+/// assert_eq!(height(&n), 3);
+/// ```
+pub struct Recursive<F, G, L, V> {
+    pub(crate) make: F,
+    pub(crate) inner: Option<G>,
+    pub(crate) branching: L,
+    pub(crate) max_depth: usize,
+    pub(crate) _result_marker: core::marker::PhantomData<V>,
+}
+
+impl<F, G, L, V> Recursive<F, G, L, V>
+where
+    F: FnMut() -> G,
+{
+    /// Create a new generator.
+    ///
+    /// Here
+    ///
+    /// - `make` creates the generator for the elements.
+    ///
+    /// - `branching` is a generator for the number of elements at the
+    ///   top level.
+    ///
+    /// - `max_depth` is the depth at which no further elements are
+    ///   generated.
+    pub fn new(make: F, branching: L, max_depth: usize) -> Self {
+        Self {
+            make,
+            inner: None,
+            branching,
+            max_depth,
+            _result_marker: Default::default(),
+        }
+    }
+
+    pub(crate) fn enter(&mut self, branching: usize) -> (usize, &mut G, DepthGuard) {
+        let depth = recursion_depth();
+        let count = if depth >= self.max_depth {
+            0
+        } else {
+            branching * (self.max_depth - depth) / self.max_depth
+        };
+        let inner = self.inner.get_or_insert_with(&mut self.make);
+        DEPTH.with(|d| d.set(depth + 1));
+        (count, inner, DepthGuard(depth))
+    }
+}
+
+/// The current depth of recursive generation.
+///
+/// This is 0 outside of any [`Recursive`] generator, and one more for
+/// each level of nesting within one. Generators for other fields of
+/// recursive types can use this to vary their output with depth, as
+/// in `#[boulder(generator = boulder::recursion_depth)]`.
+pub fn recursion_depth() -> usize {
+    DEPTH.with(|d| d.get())
+}
+
+impl<F, G, L, V, X> Generator for Recursive<F, G, L, V>
+where
+    F: FnMut() -> G + 'static,
+    G: Generator<Output = X>,
+    L: Generator<Output = usize>,
+    V: FromIterator<X> + 'static,
+{
+    type Output = V;
+    fn generate(&mut self) -> Self::Output {
+        let branching = self.branching.generate();
+        let (count, inner, _guard) = self.enter(branching);
+        super::GeneratorMutIterator { gen: inner }
+            .take(count)
+            .collect()
+    }
+}
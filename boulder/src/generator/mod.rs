@@ -1,4 +1,7 @@
 pub mod generators;
+pub(crate) mod rng;
+
+use crate::BoulderWrapper;
 
 /// Something which can generate a sequence of objects of some type.
 ///
@@ -13,6 +16,12 @@ pub mod generators;
 /// allow you to set a generator for the field. It will produce a
 /// default sequence (as configured by the attributes placed on the
 /// type) for every field that is not customised.
+#[cfg_attr(
+    feature = "async",
+    diagnostic::on_unimplemented(
+        note = "types with `default_async` fields only have an `AsyncGenerator`: use `async_generator()` and `AsyncGenerator::generate`"
+    )
+)]
 pub trait Generator
 where
     Self: 'static,
@@ -42,19 +51,31 @@ where
     /// assert_eq!(g.generate(), 7);
     /// ```
     fn generate(&mut self) -> Self::Output;
+
+    /// Prepare to generate the elements of a new collection.
+    ///
+    /// This is called when the generator is used with
+    /// `sequence_generator`, before each collection's elements are
+    /// generated. The default implementation does nothing; a
+    /// generator whose output depends on its position in the
+    /// collection, such as [`StateMachine`](crate::StateMachine),
+    /// can use it to start again.
+    fn begin_sequence(&mut self) {}
 }
 
 /// A type that has an associated default [`Generator`]
 ///
 /// This trait is implemented via the [`macro@Generatable`] derive
-/// macro. It cannot be directly implemented because the library
-/// itself provides a blanket implementation from a more complex
-/// underlying trait `MiniGeneratable`, which is not currently
-/// documented.
-///
-/// This restriction may be removed in a future version; much of the
-/// complexity in this module stems from lacking generic associated
-/// types on stable.
+/// macro. To write a generator by hand, implement
+/// [`GeneratableBase`] rather than this trait: the library provides a
+/// blanket implementation of this trait from it, which also covers
+/// every [`BoulderWrapper`](crate::BoulderWrapper) around your type.
+#[cfg_attr(
+    feature = "async",
+    diagnostic::on_unimplemented(
+        note = "types with `default_async` fields only have an `AsyncGenerator`: use `async_generator()` and `AsyncGenerator::generate`"
+    )
+)]
 pub trait Generatable {
     /// A default choice of [`Generator`] for this type.
     type Generator: Generator<Output = Self>;
@@ -62,7 +83,7 @@ pub trait Generatable {
     ///
     /// Example
     /// ```rust
-    /// use boulder::{Generatable, Generator};
+    /// use boulder::{Generatable, GeneratableBase, Generator};
     ///
     /// struct FooGenerator {
     ///   a: i32
@@ -80,9 +101,11 @@ pub trait Generatable {
     ///   a: i32
     /// };
     ///
-    /// impl Generatable for Foo {
-    ///   type Generator = FooGenerator;
-    ///   fn generator() -> Self::Generator {
+    /// // This generator only produces `Foo`, so wrappers of `Foo`
+    /// // won't pass through; see `GeneratableBase` for one which does.
+    /// impl GeneratableBase for Foo {
+    ///   type Generator<T> = FooGenerator;
+    ///   fn generator_for<T>() -> FooGenerator {
     ///     FooGenerator { a: 0 }
     ///   }
     /// }
@@ -190,75 +213,103 @@ where
     }
 }
 
-pub use boulder_derive::Generatable;
-
-#[doc(hidden)]
-pub mod guts {
-    use super::Generatable;
-    pub use super::Generator as MiniGenerator;
-
-    use std::cell::{Cell, RefCell};
-    use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
-
-    pub trait MiniGeneratable<T>: Sized {
-        type Generator: MiniGenerator<Output = Self>;
-        fn mini_generator() -> Self::Generator;
-    }
-
-    impl<T> Generatable for T
-    where
-        T: BoulderBase,
-        T: MiniGeneratable<<T as BoulderBase>::Base>,
-    {
-        type Generator = <T as MiniGeneratable<<T as BoulderBase>::Base>>::Generator;
-        fn generator() -> Self::Generator {
-            <T as MiniGeneratable<<T as BoulderBase>::Base>>::mini_generator()
-        }
-    }
-    pub trait BoulderBase {
-        type Base;
-    }
-
-    impl<T> BoulderBase for Option<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
-
-    impl<T> BoulderBase for Arc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+/// The family of generators for a type and for wrappers around it.
+///
+/// This is the supported way to give a type a hand-written
+/// generator. The [`macro@Generatable`] derive macro implements this
+/// trait too, so hand-written and derived generators compose in the
+/// same way: any type implementing it is [`Generatable`], with
+/// generator `Self::Generator<Self>`, and so is any
+/// [`BoulderWrapper`](crate::BoulderWrapper) `W` around it, with
+/// generator `Self::Generator<W>`, as long as that type is a
+/// [`Generator`] producing `W`.
+///
+/// A generator which can only produce `Self` may ignore the type
+/// parameter, in which case wrappers will not pass through. A
+/// generator which is generic over its result can support wrappers by
+/// generating the wrapped value and then calling
+/// [`BoulderWrapper::wrap`](crate::BoulderWrapper::wrap).
+///
+/// Example
+/// ```rust
+/// use boulder::{BoulderWrapper, Generatable, GeneratableBase, Generator};
+/// use std::marker::PhantomData;
+///
+/// struct Foo {
+///   a: i32,
+/// }
+///
+/// struct FooGenerator<T> {
+///   next: i32,
+///   _result: PhantomData<T>,
+/// }
+///
+/// impl Generator for FooGenerator<Foo> {
+///   type Output = Foo;
+///   fn generate(&mut self) -> Foo {
+///     self.next += 1;
+///     Foo { a: self.next }
+///   }
+/// }
+///
+/// impl<W> Generator for FooGenerator<W>
+/// where
+///   W: BoulderWrapper + 'static,
+///   FooGenerator<W::Inner>: Generator<Output = W::Inner>,
+/// {
+///   type Output = W;
+///   fn generate(&mut self) -> W {
+///     let mut inner = FooGenerator::<W::Inner> { next: self.next, _result: PhantomData };
+///     let value = inner.generate();
+///     self.next = inner.next;
+///     W::wrap(value)
+///   }
+/// }
+///
+/// impl GeneratableBase for Foo {
+///   type Generator<T> = FooGenerator<T>;
+///   fn generator_for<T>() -> FooGenerator<T> {
+///     FooGenerator { next: 0, _result: PhantomData }
+///   }
+/// }
+///
+/// let mut g = Option::<Box<Foo>>::generator();
+/// assert_eq!(g.generate().map(|f| f.a), Some(1));
+/// assert_eq!(g.generate().map(|f| f.a), Some(2));
+/// ```
+pub trait GeneratableBase {
+    /// The generator producing `T`, which is either this type or a
+    /// wrapper around it.
+    type Generator<T>;
+    /// Create a new default generator producing `T`.
+    fn generator_for<T>() -> Self::Generator<T>;
+}
 
-    impl<T> BoulderBase for Rc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<W> GeneratableBase for W
+where
+    W: BoulderWrapper,
+    <W as BoulderWrapper>::Inner: GeneratableBase,
+{
+    type Generator<T> = <<W as BoulderWrapper>::Inner as GeneratableBase>::Generator<T>;
+    fn generator_for<T>() -> Self::Generator<T> {
+        <<W as BoulderWrapper>::Inner as GeneratableBase>::generator_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for RefCell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<T> Generatable for T
+where
+    T: GeneratableBase,
+    <T as GeneratableBase>::Generator<T>: Generator<Output = T>,
+{
+    type Generator = <T as GeneratableBase>::Generator<T>;
+    fn generator() -> Self::Generator {
+        <T as GeneratableBase>::generator_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Cell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+pub use boulder_derive::Generatable;
 
-    impl<T> BoulderBase for Mutex<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+#[doc(hidden)]
+pub mod guts {
+    pub use super::Generator as MiniGenerator;
 }
@@ -134,30 +134,47 @@ assert_eq!(r.get(&r.get(&f).b).a, 5);
 )]
 
 mod builder;
+mod builtin;
 mod generator;
+mod repository;
+mod wrapper;
 
-pub use self::builder::{Buildable, Builder};
+pub use self::builder::{Buildable, BuildableBase, Builder};
+pub use self::builtin::{BuiltinBuilder, BuiltinGenerator};
 pub use self::generator::generators::{
-    Const, Cycle, Inc, Pattern, Repeat, Sample, Some, Subsets, Time,
+    recursion_depth, reset_sequence, reset_sequences, set_sequence, BTreeMapOf, Const, Cycle,
+    EdgeStrings, Frequency, HashSetOf, Inc, Maybe, OkOrErr, Paths, Pattern, RandomWalk, Recursive,
+    Repeat, Sample, Sequence, Shared, Sine, Some, StateMachine, Subsets, Time, Trend, Unique,
+    Weighted, WithEdgeStrings, WithEvents, Zip, DEFAULT_RETRIES,
 };
-pub use self::generator::{Generatable, Generator};
+pub use self::generator::{Generatable, GeneratableBase, Generator};
 pub use self::generator::{GeneratorIterator, GeneratorMutIterator};
+pub use self::repository::{MemoryRepository, Persist, Repository};
+pub use self::wrapper::BoulderWrapper;
 
 #[cfg(feature = "persian-rug")]
 mod persian_rug;
 #[cfg(feature = "persian-rug")]
 #[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
 pub use self::persian_rug::{
-    BuildableWithPersianRug, BuilderWithPersianRug, GeneratableWithPersianRug,
+    BuildableBaseWithPersianRug, BuildableWithPersianRug, BuilderWithPersianRug,
+    GeneratableBaseWithPersianRug, GeneratableWithPersianRug,
     GeneratorToGeneratorWithPersianRugWrapper, GeneratorWithPersianRug,
-    GeneratorWithPersianRugIterator, GeneratorWithPersianRugMutIterator, RepeatFromPersianRug,
-    SampleFromPersianRug, SequenceGeneratorWithPersianRug, SubsetsFromPersianRug,
-    TryRepeatFromPersianRug,
+    GeneratorWithPersianRugIterator, GeneratorWithPersianRugMutIterator, InsertedWithPersianRug,
+    RepeatFromPersianRug, SampleFromPersianRug, SequenceGeneratorWithPersianRug,
+    SubsetsFromPersianRug, TryRepeatFromPersianRug, WrapWithPersianRug, WrapperWithPersianRug,
 };
 
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use self::asynchronous::{AsyncBuildable, AsyncBuilder, AsyncGeneratable, AsyncGenerator};
+
 #[doc(hidden)]
 pub mod guts {
     pub use crate::builder::guts as builder;
+    pub use crate::builtin::collect_array;
     pub use crate::generator::guts as generator;
 
     #[cfg(feature = "persian-rug")]
@@ -1,3 +1,5 @@
+use crate::persian_rug::WrapperWithPersianRug;
+
 /// A type that has an associated default [`BuilderWithPersianRug`]
 ///
 /// [persian-rug](persian_rug) is a crate that provides arena based
@@ -6,14 +8,11 @@
 /// your type belongs to a [`persian_rug::Context`].
 ///
 /// This trait is implemented via the
-/// [`macro@BuildableWithPersianRug`] derive macro.  It cannot be
-/// directly implemented because the library itself provides a blanket
-/// implementation from a more complex underlying trait
-/// `MiniBuildable`, which is not currently documented.
-///
-/// This restriction may be removed in a future version; much of the
-/// complexity in this module stems from lacking generic associated
-/// types on stable.
+/// [`macro@BuildableWithPersianRug`] derive macro. To write a
+/// builder by hand, implement [`BuildableBaseWithPersianRug`] rather
+/// than this trait: the library provides a blanket implementation of
+/// this trait from it, which also covers every wrapper around your
+/// type, including [`Proxy<T>`](persian_rug::Proxy).
 #[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
 pub trait BuildableWithPersianRug<C>: Sized
 where
@@ -93,88 +92,149 @@ where
         B: 'b + persian_rug::Mutator<Context = C>;
 }
 
-pub use boulder_derive::BuildableWithPersianRug;
-
-#[doc(hidden)]
-pub mod guts {
-    use super::BuildableWithPersianRug;
-
-    use std::cell::{Cell, RefCell};
-    use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
-
-    pub use super::BuilderWithPersianRug as MiniBuilderWithPersianRug;
-
-    pub trait MiniBuildableWithPersianRug<T, C>: Sized
-    where
-        C: persian_rug::Context,
-    {
-        type Builder: MiniBuilderWithPersianRug<C, Result = Self>;
-        fn mini_builder() -> Self::Builder;
-    }
-
-    impl<T, C> BuildableWithPersianRug<C> for T
-    where
-        T: BoulderBase,
-        T: MiniBuildableWithPersianRug<<T as BoulderBase>::Base, C>,
-        C: persian_rug::Context,
-    {
-        type Builder = <T as MiniBuildableWithPersianRug<<T as BoulderBase>::Base, C>>::Builder;
-        fn builder() -> Self::Builder {
-            <T as MiniBuildableWithPersianRug<<T as BoulderBase>::Base, C>>::mini_builder()
-        }
-    }
-
-    pub trait BoulderBase {
-        type Base;
-    }
+/// The family of builders for a type and for wrappers around it,
+/// with access to a [`persian_rug::Context`].
+///
+/// This is the persian-rug counterpart of
+/// [`BuildableBase`](crate::BuildableBase), and the supported way to
+/// give a type a hand-written
+/// [`BuilderWithPersianRug`]. Any type implementing it is
+/// [`BuildableWithPersianRug`], with builder `Self::Builder<Self>`,
+/// and so is any [`WrapperWithPersianRug`](crate::WrapperWithPersianRug)
+/// `W` around it, with builder `Self::Builder<W>`, as long as that
+/// type is a [`BuilderWithPersianRug`] producing `W`.
+///
+/// Example
+/// ```rust
+/// use boulder::{
+///   BuildableBaseWithPersianRug, BuildableWithPersianRug, BuilderWithPersianRug,
+///   WrapWithPersianRug, WrapperWithPersianRug,
+/// };
+/// use persian_rug::{contextual, persian_rug, Context, Mutator, Proxy};
+/// use std::marker::PhantomData;
+///
+/// #[contextual(State)]
+/// struct Foo {
+///   a: i32,
+/// }
+///
+/// #[persian_rug]
+/// struct State(#[table] Foo);
+///
+/// struct FooBuilder<T> {
+///   a: i32,
+///   _result: PhantomData<T>,
+/// }
+///
+/// impl BuilderWithPersianRug<State> for FooBuilder<Foo> {
+///   type Result = Foo;
+///   fn build<'b, B>(self, context: B) -> (Foo, B)
+///   where
+///     B: 'b + Mutator<Context = State>,
+///   {
+///     (Foo { a: self.a }, context)
+///   }
+/// }
+///
+/// impl<W> BuilderWithPersianRug<State> for FooBuilder<W>
+/// where
+///   W: WrapperWithPersianRug + WrapWithPersianRug<State>,
+///   FooBuilder<W::Inner>: BuilderWithPersianRug<State, Result = W::Inner>,
+/// {
+///   type Result = W;
+///   fn build<'b, B>(self, context: B) -> (W, B)
+///   where
+///     B: 'b + Mutator<Context = State>,
+///   {
+///     let inner = FooBuilder::<W::Inner> { a: self.a, _result: PhantomData };
+///     let (value, context) = inner.build(context);
+///     W::wrap(value, context)
+///   }
+/// }
+///
+/// impl BuildableBaseWithPersianRug for Foo {
+///   type Builder<T> = FooBuilder<T>;
+///   fn builder_for<T>() -> FooBuilder<T> {
+///     FooBuilder { a: 1, _result: PhantomData }
+///   }
+/// }
+///
+/// let mut s = State(Default::default());
+/// let (p, _) = Proxy::<Foo>::builder().build(&mut s);
+/// assert_eq!(s.get(&p).a, 1);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
+pub trait BuildableBaseWithPersianRug {
+    /// The builder producing `T`, which is either this type or a
+    /// wrapper around it.
+    type Builder<T>;
+    /// Create a new default builder producing `T`.
+    fn builder_for<T>() -> Self::Builder<T>;
+}
 
-    impl<T> BoulderBase for Option<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<W> BuildableBaseWithPersianRug for W
+where
+    W: WrapperWithPersianRug,
+    <W as WrapperWithPersianRug>::Inner: BuildableBaseWithPersianRug,
+{
+    type Builder<T> =
+        <<W as WrapperWithPersianRug>::Inner as BuildableBaseWithPersianRug>::Builder<T>;
+    fn builder_for<T>() -> Self::Builder<T> {
+        <<W as WrapperWithPersianRug>::Inner as BuildableBaseWithPersianRug>::builder_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Arc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<T, C> BuildableWithPersianRug<C> for T
+where
+    C: persian_rug::Context,
+    T: BuildableBaseWithPersianRug,
+    <T as BuildableBaseWithPersianRug>::Builder<T>: BuilderWithPersianRug<C, Result = T>,
+{
+    type Builder = <T as BuildableBaseWithPersianRug>::Builder<T>;
+    fn builder() -> Self::Builder {
+        <T as BuildableBaseWithPersianRug>::builder_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Rc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+pub use boulder_derive::BuildableWithPersianRug;
 
-    impl<T> BoulderBase for RefCell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+#[doc(hidden)]
+pub mod guts {
+    pub use super::BuilderWithPersianRug as MiniBuilderWithPersianRug;
 
-    impl<T> BoulderBase for Cell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+    use super::BuildableWithPersianRug;
 
-    impl<T> BoulderBase for Mutex<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+    /// A customisation of the builder for a nested field of type `T`,
+    /// as passed to a derived `<field>_with` method.
+    ///
+    /// The builder is only named inside the method, so that derived
+    /// builders can store a customisation without requiring
+    /// `T: BuildableWithPersianRug<C>` in their own bounds.
+    pub trait Customisation<C, T>: Send + Sync {
+        fn customise(
+            &self,
+            builder: <T as BuildableWithPersianRug<C>>::Builder,
+        ) -> <T as BuildableWithPersianRug<C>>::Builder
+        where
+            C: persian_rug::Context,
+            T: BuildableWithPersianRug<C>;
     }
 
-    impl<T> BoulderBase for persian_rug::Proxy<T>
+    impl<C, T, F> Customisation<C, T> for F
     where
-        T: BoulderBase,
+        C: persian_rug::Context,
+        T: BuildableWithPersianRug<C>,
+        F: Fn(
+                <T as BuildableWithPersianRug<C>>::Builder,
+            ) -> <T as BuildableWithPersianRug<C>>::Builder
+            + Send
+            + Sync,
     {
-        type Base = <T as BoulderBase>::Base;
+        fn customise(
+            &self,
+            builder: <T as BuildableWithPersianRug<C>>::Builder,
+        ) -> <T as BuildableWithPersianRug<C>>::Builder {
+            self(builder)
+        }
     }
 }
@@ -1,15 +1,13 @@
+use crate::persian_rug::WrapperWithPersianRug;
+
 /// A type that has an associated default [`GeneratorWithPersianRug`]
 ///
 /// This trait is implemented via the
-/// [`macro@GeneratableWithPersianRug`] derive macro. It cannot be
-/// directly implemented because the library itself provides a blanket
-/// implementation from a more complex underlying trait
-/// `MiniGeneratableWithPersianRug`, which is not currently
-/// documented.
-///
-/// This restriction may be removed in a future version; much of the
-/// complexity in this module stems from lacking generic associated
-/// types on stable.
+/// [`macro@GeneratableWithPersianRug`] derive macro. It can also be
+/// implemented directly, but then wrappers such as
+/// [`Proxy<T>`](persian_rug::Proxy) will not pass through. Implement
+/// [`GeneratableBaseWithPersianRug`] instead to get that: the library
+/// provides a blanket implementation of this trait from it.
 #[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
 pub trait GeneratableWithPersianRug<C>
 where
@@ -138,6 +136,15 @@ where
     fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
     where
         B: 'b + persian_rug::Mutator<Context = C>;
+
+    /// Prepare to generate the elements of a new collection.
+    ///
+    /// This is the counterpart of
+    /// [`Generator::begin_sequence`](crate::Generator::begin_sequence),
+    /// called by [`SequenceGeneratorWithPersianRug`] before each
+    /// collection's elements are generated. The default
+    /// implementation does nothing.
+    fn begin_sequence(&mut self) {}
 }
 
 /// An owning iterator for any generator.
@@ -337,6 +344,7 @@ where
         B: 'b + persian_rug::Mutator<Context = C>,
     {
         let (count, mut context) = self.seq.generate(context);
+        self.elt.begin_sequence();
         let mut storage = Vec::new();
         for _ in 0usize..count.into() {
             let (value, c) = self.elt.generate(context);
@@ -373,6 +381,10 @@ where
     {
         (self.gen.generate(), context)
     }
+
+    fn begin_sequence(&mut self) {
+        self.gen.begin_sequence();
+    }
 }
 
 /// Cycle through the existing [`Proxy<T>`](persian_rug::Proxy) items
@@ -613,9 +625,9 @@ impl<C, T> GeneratorWithPersianRug<C> for SubsetsFromPersianRug<T> {
     {
         let mut res = Vec::new();
         let proxies = context.get_proxy_iter().collect::<Vec<_>>();
-        for i in 0..std::cmp::min(64, proxies.len()) {
+        for (i, proxy) in proxies.iter().enumerate().take(64) {
             if (self.index & (1u64 << i)) != 0 {
-                res.push(*proxies[i as usize]);
+                res.push(**proxy);
             }
         }
         self.index += 1;
@@ -706,97 +718,69 @@ where
     }
 }
 
-pub use boulder_derive::GeneratableWithPersianRug;
-
-#[doc(hidden)]
-pub mod guts {
-    use super::GeneratableWithPersianRug;
-
-    use std::cell::{Cell, RefCell};
-    use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
-
-    pub use super::GeneratorWithPersianRug as MiniGeneratorWithPersianRug;
-
-    pub trait MiniGeneratableWithPersianRug<T, C>: Sized
-    where
-        C: persian_rug::Context,
-    {
-        type Generator: MiniGeneratorWithPersianRug<C, Output = Self>;
-        fn mini_generator() -> Self::Generator;
-    }
-
-    impl<T, C> GeneratableWithPersianRug<C> for T
-    where
-        T: BoulderBase,
-        T: MiniGeneratableWithPersianRug<<T as BoulderBase>::Base, C>,
-        C: persian_rug::Context,
-    {
-        type Generator =
-            <T as MiniGeneratableWithPersianRug<<T as BoulderBase>::Base, C>>::Generator;
-        fn generator() -> Self::Generator {
-            <T as MiniGeneratableWithPersianRug<<T as BoulderBase>::Base, C>>::mini_generator()
-        }
-    }
-
-    pub trait BoulderBase {
-        type Base;
-    }
-
-    impl<T> BoulderBase for Option<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
-
-    impl<T> BoulderBase for Arc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
-
-    impl<T> BoulderBase for Rc<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+/// The family of generators for a type and for wrappers around it,
+/// with access to a [`persian_rug::Context`].
+///
+/// This is the persian-rug counterpart of
+/// [`GeneratableBase`](crate::GeneratableBase). Any type implementing
+/// it is [`GeneratableWithPersianRug`], with generator
+/// `Self::Generator<Self>`, and so is any
+/// [`WrapperWithPersianRug`](crate::WrapperWithPersianRug) `W` around
+/// it, with generator `Self::Generator<W>`, as long as that type is a
+/// [`GeneratorWithPersianRug`] producing `W`. See
+/// [`BuildableBaseWithPersianRug`](crate::BuildableBaseWithPersianRug)
+/// for an example of the pattern.
+#[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
+pub trait GeneratableBaseWithPersianRug {
+    /// The generator producing `T`, which is either this type or a
+    /// wrapper around it.
+    type Generator<T>;
+    /// Create a new default generator producing `T`.
+    fn generator_for<T>() -> Self::Generator<T>;
+}
 
-    impl<T> BoulderBase for RefCell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<W> GeneratableBaseWithPersianRug for W
+where
+    W: WrapperWithPersianRug,
+    <W as WrapperWithPersianRug>::Inner: GeneratableBaseWithPersianRug,
+{
+    type Generator<T> =
+        <<W as WrapperWithPersianRug>::Inner as GeneratableBaseWithPersianRug>::Generator<T>;
+    fn generator_for<T>() -> Self::Generator<T> {
+        <<W as WrapperWithPersianRug>::Inner as GeneratableBaseWithPersianRug>::generator_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Cell<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
+impl<T, C> GeneratableWithPersianRug<C> for T
+where
+    C: persian_rug::Context,
+    T: GeneratableBaseWithPersianRug,
+    <T as GeneratableBaseWithPersianRug>::Generator<T>: GeneratorWithPersianRug<C, Output = T>,
+{
+    type Generator = <T as GeneratableBaseWithPersianRug>::Generator<T>;
+    fn generator() -> Self::Generator {
+        <T as GeneratableBaseWithPersianRug>::generator_for::<T>()
     }
+}
 
-    impl<T> BoulderBase for Mutex<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+pub use boulder_derive::GeneratableWithPersianRug;
 
-    impl<T> BoulderBase for persian_rug::Proxy<T>
-    where
-        T: BoulderBase,
-    {
-        type Base = <T as BoulderBase>::Base;
-    }
+#[doc(hidden)]
+pub mod guts {
+    pub use super::GeneratorWithPersianRug as MiniGeneratorWithPersianRug;
 }
 
 mod gen {
     use super::GeneratorWithPersianRug;
-    use crate::{Const, Cycle, Inc, Repeat, Sample, Some, Subsets, Time};
+    use crate::generator::generators::exhausted;
+    use crate::{
+        BTreeMapOf, Const, Cycle, EdgeStrings, HashSetOf, Inc, Maybe, OkOrErr, Paths, RandomWalk,
+        Recursive, Repeat, Sample, Sequence, Shared, Sine, Some, StateMachine, Subsets, Time,
+        Trend, Unique, Weighted, WithEdgeStrings, WithEvents, Zip,
+    };
     use num::One;
+    use std::collections::{BTreeMap, HashSet};
+    use std::hash::Hash;
 
     impl<C, T> GeneratorWithPersianRug<C> for Const<T>
     where
@@ -945,4 +929,338 @@ mod gen {
             (res, context)
         }
     }
+
+    impl<C> GeneratorWithPersianRug<C> for EdgeStrings
+    where
+        C: persian_rug::Context,
+    {
+        type Output = String;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (crate::Generator::generate(self), context)
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for WithEdgeStrings<T>
+    where
+        T: GeneratorWithPersianRug<C, Output = String>,
+        C: persian_rug::Context,
+    {
+        type Output = String;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            self.index = (self.index + 1) % self.every;
+            if self.index == 0 {
+                (crate::Generator::generate(&mut self.edges), context)
+            } else {
+                self.inner.generate(context)
+            }
+        }
+    }
+
+    impl<C, T, U> GeneratorWithPersianRug<C> for Unique<T, U>
+    where
+        T: GeneratorWithPersianRug<C, Output = U>,
+        U: Hash + Eq + Clone + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = U;
+        fn generate<'b, B>(&mut self, mut context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            for _ in 0..=self.retries {
+                let (value, c) = self.inner.generate(context);
+                context = c;
+                if let Option::Some(value) = self.accept(value) {
+                    return (value, context);
+                }
+            }
+            exhausted::<U>("Unique", self.retries)
+        }
+    }
+
+    impl<C, T, U, X> GeneratorWithPersianRug<C> for HashSetOf<T, U>
+    where
+        T: GeneratorWithPersianRug<C, Output = X>,
+        U: GeneratorWithPersianRug<C, Output = usize>,
+        X: Hash + Eq + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = HashSet<X>;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (count, mut context) = self.count.generate(context);
+            let mut res = HashSet::with_capacity(count);
+            let mut failures = 0;
+            while res.len() < count {
+                let (value, c) = self.value.generate(context);
+                context = c;
+                if res.insert(value) {
+                    failures = 0;
+                } else if failures == self.retries {
+                    exhausted::<X>("HashSetOf", self.retries)
+                } else {
+                    failures += 1;
+                }
+            }
+            (res, context)
+        }
+    }
+
+    impl<C, K, V, U, X, Y> GeneratorWithPersianRug<C> for BTreeMapOf<K, V, U>
+    where
+        K: GeneratorWithPersianRug<C, Output = X>,
+        V: GeneratorWithPersianRug<C, Output = Y>,
+        U: GeneratorWithPersianRug<C, Output = usize>,
+        X: Ord + 'static,
+        Y: 'static,
+        C: persian_rug::Context,
+    {
+        type Output = BTreeMap<X, Y>;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (count, mut context) = self.count.generate(context);
+            let mut res = BTreeMap::new();
+            let mut failures = 0;
+            while res.len() < count {
+                let (key, c) = self.key.generate(context);
+                context = c;
+                if let std::collections::btree_map::Entry::Vacant(e) = res.entry(key) {
+                    let (value, c) = self.value.generate(context);
+                    context = c;
+                    e.insert(value);
+                    failures = 0;
+                } else if failures == self.retries {
+                    exhausted::<X>("BTreeMapOf", self.retries)
+                } else {
+                    failures += 1;
+                }
+            }
+            (res, context)
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for Weighted<T>
+    where
+        T: Clone + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = T;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let index = self.next_index();
+            (self.base[index].0.clone(), context)
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for Maybe<T>
+    where
+        T: GeneratorWithPersianRug<C>,
+        C: persian_rug::Context,
+    {
+        type Output = Option<<T as GeneratorWithPersianRug<C>>::Output>;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            if self.schedule.next() {
+                (None, context)
+            } else {
+                let (value, context) = self.inner.generate(context);
+                (Option::Some(value), context)
+            }
+        }
+    }
+
+    impl<C, T, E> GeneratorWithPersianRug<C> for OkOrErr<T, E>
+    where
+        T: GeneratorWithPersianRug<C>,
+        E: GeneratorWithPersianRug<C>,
+        C: persian_rug::Context,
+    {
+        type Output = Result<
+            <T as GeneratorWithPersianRug<C>>::Output,
+            <E as GeneratorWithPersianRug<C>>::Output,
+        >;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            if self.schedule.next() {
+                let (value, context) = self.err.generate(context);
+                (Err(value), context)
+            } else {
+                let (value, context) = self.ok.generate(context);
+                (Ok(value), context)
+            }
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for Shared<T>
+    where
+        T: GeneratorWithPersianRug<C>,
+        C: persian_rug::Context,
+    {
+        type Output = <T as GeneratorWithPersianRug<C>>::Output;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            self.inner.borrow_mut().generate(context)
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for Sequence<T>
+    where
+        T: num::FromPrimitive + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = T;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (self.next_value(), context)
+        }
+    }
+
+    impl<C, S> GeneratorWithPersianRug<C> for StateMachine<S>
+    where
+        S: Clone + PartialEq + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = S;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (self.next_state(), context)
+        }
+    }
+
+    impl<C, S, L> GeneratorWithPersianRug<C> for Paths<S, L>
+    where
+        S: Clone + PartialEq + 'static,
+        L: GeneratorWithPersianRug<C, Output = usize>,
+        C: persian_rug::Context,
+    {
+        type Output = Vec<S>;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (length, context) = self.lengths.generate(context);
+            (self.next_path(length), context)
+        }
+    }
+
+    impl<C, T, U> GeneratorWithPersianRug<C> for Zip<T, U>
+    where
+        T: GeneratorWithPersianRug<C>,
+        U: GeneratorWithPersianRug<C>,
+        C: persian_rug::Context,
+    {
+        type Output = (
+            <T as GeneratorWithPersianRug<C>>::Output,
+            <U as GeneratorWithPersianRug<C>>::Output,
+        );
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (first, context) = self.first.generate(context);
+            let (second, context) = self.second.generate(context);
+            ((first, second), context)
+        }
+    }
+
+    impl<C> GeneratorWithPersianRug<C> for RandomWalk
+    where
+        C: persian_rug::Context,
+    {
+        type Output = f64;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (self.next_value(), context)
+        }
+    }
+
+    impl<C> GeneratorWithPersianRug<C> for Sine
+    where
+        C: persian_rug::Context,
+    {
+        type Output = f64;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (self.next_value(), context)
+        }
+    }
+
+    impl<C> GeneratorWithPersianRug<C> for Trend
+    where
+        C: persian_rug::Context,
+    {
+        type Output = f64;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            (self.next_value(), context)
+        }
+    }
+
+    impl<C, T> GeneratorWithPersianRug<C> for WithEvents<T>
+    where
+        T: GeneratorWithPersianRug<C, Output = f64>,
+        C: persian_rug::Context,
+    {
+        type Output = f64;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (value, context) = self.inner.generate(context);
+            (value + self.next_offset(), context)
+        }
+    }
+
+    impl<C, F, G, L, V, X> GeneratorWithPersianRug<C> for Recursive<F, G, L, V>
+    where
+        F: FnMut() -> G + 'static,
+        G: GeneratorWithPersianRug<C, Output = X>,
+        L: GeneratorWithPersianRug<C, Output = usize>,
+        V: FromIterator<X> + 'static,
+        C: persian_rug::Context,
+    {
+        type Output = V;
+        fn generate<'b, B>(&mut self, context: B) -> (Self::Output, B)
+        where
+            B: 'b + persian_rug::Mutator<Context = C>,
+        {
+            let (branching, mut context) = self.branching.generate(context);
+            let (count, inner, _guard) = self.enter(branching);
+            let mut res = Vec::with_capacity(count);
+            for _ in 0..count {
+                let (value, c) = inner.generate(context);
+                res.push(value);
+                context = c;
+            }
+            (res.into_iter().collect(), context)
+        }
+    }
 }
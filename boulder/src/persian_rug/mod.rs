@@ -1,10 +1,13 @@
 pub mod builder;
 pub mod generator;
+mod wrapper;
 
-pub use builder::{BuildableWithPersianRug, BuilderWithPersianRug};
+pub use builder::{BuildableBaseWithPersianRug, BuildableWithPersianRug, BuilderWithPersianRug};
 pub use generator::{
-    GeneratableWithPersianRug, GeneratorToGeneratorWithPersianRugWrapper, GeneratorWithPersianRug,
+    GeneratableBaseWithPersianRug, GeneratableWithPersianRug,
+    GeneratorToGeneratorWithPersianRugWrapper, GeneratorWithPersianRug,
     GeneratorWithPersianRugIterator, GeneratorWithPersianRugMutIterator, RepeatFromPersianRug,
     SampleFromPersianRug, SequenceGeneratorWithPersianRug, SubsetsFromPersianRug,
     TryRepeatFromPersianRug,
 };
+pub use wrapper::{InsertedWithPersianRug, WrapWithPersianRug, WrapperWithPersianRug};
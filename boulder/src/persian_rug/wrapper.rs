@@ -0,0 +1,100 @@
+use crate::BoulderWrapper;
+
+// These are split in two so that the derived impls for a base type
+// can be told apart from the wrapper impls without reference to the
+// context type: a downstream crate could otherwise implement the
+// context-parameterised trait for any derived type.
+
+/// A type which builders and generators with a
+/// [`persian_rug::Context`] can pass through.
+///
+/// This is implemented for every
+/// [`BoulderWrapper`](crate::BoulderWrapper), and for
+/// [`Proxy<T>`](persian_rug::Proxy). The construction itself is
+/// provided by [`WrapWithPersianRug`].
+#[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
+pub trait WrapperWithPersianRug {
+    /// The type being wrapped.
+    type Inner;
+}
+
+/// Construct a [`WrapperWithPersianRug`] using a
+/// [`persian_rug::Context`].
+///
+/// For a [`BoulderWrapper`](crate::BoulderWrapper) this ignores the
+/// context; for [`Proxy<T>`](persian_rug::Proxy) it adds the value to
+/// the context.
+#[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
+pub trait WrapWithPersianRug<C>: WrapperWithPersianRug + Sized
+where
+    C: persian_rug::Context,
+{
+    /// Wrap a freshly built or generated value.
+    fn wrap<'b, B>(inner: Self::Inner, context: B) -> (Self, B)
+    where
+        B: 'b + persian_rug::Mutator<Context = C>;
+}
+
+impl<T> WrapperWithPersianRug for T
+where
+    T: BoulderWrapper,
+{
+    type Inner = <T as BoulderWrapper>::Inner;
+}
+
+impl<C, T> WrapWithPersianRug<C> for T
+where
+    C: persian_rug::Context,
+    T: BoulderWrapper,
+{
+    fn wrap<'b, B>(inner: Self::Inner, context: B) -> (Self, B)
+    where
+        B: 'b + persian_rug::Mutator<Context = C>,
+    {
+        (<T as BoulderWrapper>::wrap(inner), context)
+    }
+}
+
+impl<T> WrapperWithPersianRug for persian_rug::Proxy<T> {
+    type Inner = T;
+}
+
+impl<C, T> WrapWithPersianRug<C> for persian_rug::Proxy<T>
+where
+    C: persian_rug::Context + persian_rug::Owner<T>,
+    T: persian_rug::Contextual<Context = C>,
+{
+    fn wrap<'b, B>(inner: T, mut context: B) -> (Self, B)
+    where
+        B: 'b + persian_rug::Mutator<Context = C>,
+    {
+        (context.add(inner), context)
+    }
+}
+
+/// Recover the [`Proxy<T>`](persian_rug::Proxy) for a value which
+/// [`WrapWithPersianRug`] added to the context.
+///
+/// The derived builders and generators use this to run
+/// `after_insert_with_persian_rug` hooks, which only apply when the
+/// result is a proxy.
+#[cfg_attr(docsrs, doc(cfg(feature = "persian-rug")))]
+pub trait InsertedWithPersianRug<T> {
+    /// The proxy for the newly added value, if there is one.
+    fn inserted(&self) -> Option<&persian_rug::Proxy<T>>;
+}
+
+impl<T, W> InsertedWithPersianRug<T> for W
+where
+    W: BoulderWrapper,
+{
+    fn inserted(&self) -> Option<&persian_rug::Proxy<T>> {
+        None
+    }
+}
+
+impl<T> InsertedWithPersianRug<T> for persian_rug::Proxy<T> {
+    fn inserted(&self) -> Option<&persian_rug::Proxy<T>> {
+        Some(self)
+    }
+}
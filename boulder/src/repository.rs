@@ -0,0 +1,365 @@
+//! Storing built values, for builders and generators which create
+//! rather than just build.
+
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Deref;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Somewhere to store values of type `T`.
+///
+/// The derived builders and generators have a `create(&mut repo)`
+/// method, which builds or generates a value, stores it with
+/// [`insert`](Repository::insert), and returns whatever that
+/// returns. Implement this for your own store, once for each type
+/// it holds; [`MemoryRepository`] holds values of any type in
+/// memory.
+///
+/// Example
+/// ```rust
+/// use boulder::{Buildable, Repository};
+///
+/// #[derive(Buildable)]
+/// struct Widget {
+///     #[boulder(default = "gear")]
+///     name: String,
+/// }
+///
+/// struct Names(Vec<String>);
+///
+/// impl Repository<Widget> for Names {
+///     type Output = String;
+///     fn insert(&mut self, value: &Widget) -> String {
+///         self.0.push(value.name.clone());
+///         format!("widget/{}", self.0.len())
+///     }
+/// }
+///
+/// let mut names = Names(Vec::new());
+/// assert_eq!(Widget::builder().create(&mut names), "widget/1");
+/// assert_eq!(names.0, vec!["gear".to_string()]);
+/// ```
+pub trait Repository<T> {
+    /// What storing a value returns, for example its ID, or the
+    /// stored value.
+    type Output;
+    /// Store a copy of `value`.
+    fn insert(&mut self, value: &T) -> Self::Output;
+}
+
+/// A value which can store itself, and the values it was built
+/// from, in a repository of type `R`.
+///
+/// Deriving [`Buildable`](crate::Buildable) implements this for the
+/// type, storing the fields marked `buildable` first, and then the
+/// value itself. A `buildable` field with a `#[cfg]` attribute is
+/// skipped, since it may not exist, so its value is never stored.
+/// Standard library and chrono types store nothing themselves, but
+/// collections, tuples, and the wrappers which builders pass through,
+/// such as [`Option`], [`Box`], [`Rc`], [`Mutex`] and [`RefCell`],
+/// store their contents. A [`Cell`] can only store a `Copy` value.
+pub trait Persist<R> {
+    /// Store this value and its nested values in `repo`, nested
+    /// values first.
+    fn persist(&self, repo: &mut R);
+}
+
+/// A [`Repository`] for values of any type, held in memory.
+///
+/// Each value is stored as a clone, and its ID is its position
+/// among the values of its type, counting from zero.
+///
+/// Example
+/// ```rust
+/// use boulder::{Buildable, MemoryRepository};
+///
+/// #[derive(Buildable, Clone)]
+/// struct Customer {
+///     #[boulder(default = "Ada")]
+///     name: String,
+/// }
+///
+/// #[derive(Buildable, Clone)]
+/// struct Order {
+///     #[boulder(buildable)]
+///     customer: Customer,
+///     quantity: u32,
+/// }
+///
+/// let mut repo = MemoryRepository::new();
+/// let id = Order::builder().quantity(3u32).create(&mut repo);
+/// assert_eq!(repo.get::<Order>(id).unwrap().quantity, 3);
+/// assert_eq!(repo.len::<Customer>(), 1);
+/// assert_eq!(repo.all::<Customer>().next().unwrap().name, "Ada");
+/// ```
+#[derive(Default)]
+pub struct MemoryRepository {
+    tables: HashMap<TypeId, Vec<Box<dyn Any>>>,
+}
+
+impl MemoryRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored value of type `T` with the given ID, if there is
+    /// one.
+    pub fn get<T: 'static>(&self, id: usize) -> Option<&T> {
+        self.tables
+            .get(&TypeId::of::<T>())
+            .and_then(|table| table.get(id))
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// All the stored values of type `T`, in the order they were
+    /// stored.
+    pub fn all<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.tables
+            .get(&TypeId::of::<T>())
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.downcast_ref())
+    }
+
+    /// The number of stored values of type `T`.
+    pub fn len<T: 'static>(&self) -> usize {
+        self.tables
+            .get(&TypeId::of::<T>())
+            .map_or(0, |table| table.len())
+    }
+
+    /// Whether nothing at all has been stored.
+    pub fn is_empty(&self) -> bool {
+        self.tables.values().all(|table| table.is_empty())
+    }
+}
+
+impl<T> Repository<T> for MemoryRepository
+where
+    T: Clone + 'static,
+{
+    type Output = usize;
+    fn insert(&mut self, value: &T) -> usize {
+        let table = self.tables.entry(TypeId::of::<T>()).or_default();
+        table.push(Box::new(value.clone()));
+        table.len() - 1
+    }
+}
+
+macro_rules! leaves {
+    ($($ty:ty),*) => {
+        $(
+            impl<R> Persist<R> for $ty {
+                fn persist(&self, _repo: &mut R) {}
+            }
+        )*
+    };
+}
+
+leaves!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    String,
+    chrono::NaiveDateTime,
+    chrono::NaiveDate,
+    chrono::NaiveTime,
+    chrono::DateTime<chrono::Utc>,
+    chrono::DateTime<chrono::FixedOffset>
+);
+
+macro_rules! containers {
+    ($($ty:ident),*) => {
+        $(
+            impl<R, X> Persist<R> for $ty<X>
+            where
+                X: Persist<R>,
+            {
+                fn persist(&self, repo: &mut R) {
+                    for item in self {
+                        item.persist(repo);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+containers!(Vec, VecDeque, HashSet, BTreeSet);
+
+impl<R, X> Persist<R> for Option<X>
+where
+    X: Persist<R>,
+{
+    fn persist(&self, repo: &mut R) {
+        if let Some(item) = self {
+            item.persist(repo);
+        }
+    }
+}
+
+macro_rules! maps {
+    ($($ty:ident),*) => {
+        $(
+            impl<R, K, X> Persist<R> for $ty<K, X>
+            where
+                K: Persist<R>,
+                X: Persist<R>,
+            {
+                fn persist(&self, repo: &mut R) {
+                    for (key, item) in self {
+                        key.persist(repo);
+                        item.persist(repo);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+maps!(HashMap, BTreeMap);
+
+impl<R, X, const N: usize> Persist<R> for [X; N]
+where
+    X: Persist<R>,
+{
+    fn persist(&self, repo: &mut R) {
+        for item in self {
+            item.persist(repo);
+        }
+    }
+}
+
+macro_rules! pointers {
+    ($($ty:ty),*) => {
+        $(
+            impl<R, X> Persist<R> for $ty
+            where
+                X: Persist<R> + ?Sized,
+            {
+                fn persist(&self, repo: &mut R) {
+                    (**self).persist(repo);
+                }
+            }
+        )*
+    };
+}
+
+pointers!(Box<X>, Rc<X>, Arc<X>);
+
+impl<R, X> Persist<R> for Cow<'static, X>
+where
+    X: Persist<R> + ToOwned + ?Sized,
+{
+    fn persist(&self, repo: &mut R) {
+        (**self).persist(repo);
+    }
+}
+
+impl<R, P> Persist<R> for Pin<P>
+where
+    P: Deref,
+    P::Target: Persist<R>,
+{
+    fn persist(&self, repo: &mut R) {
+        (**self).persist(repo);
+    }
+}
+
+// A poisoned lock still holds the value that was built.
+impl<R, X> Persist<R> for Mutex<X>
+where
+    X: Persist<R> + ?Sized,
+{
+    fn persist(&self, repo: &mut R) {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .persist(repo);
+    }
+}
+
+impl<R, X> Persist<R> for RwLock<X>
+where
+    X: Persist<R> + ?Sized,
+{
+    fn persist(&self, repo: &mut R) {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .persist(repo);
+    }
+}
+
+impl<R, X> Persist<R> for Cell<X>
+where
+    X: Persist<R> + Copy,
+{
+    fn persist(&self, repo: &mut R) {
+        self.get().persist(repo);
+    }
+}
+
+impl<R, X> Persist<R> for RefCell<X>
+where
+    X: Persist<R> + ?Sized,
+{
+    fn persist(&self, repo: &mut R) {
+        self.borrow().persist(repo);
+    }
+}
+
+impl<R, X> Persist<R> for OnceCell<X>
+where
+    X: Persist<R>,
+{
+    fn persist(&self, repo: &mut R) {
+        if let Some(item) = self.get() {
+            item.persist(repo);
+        }
+    }
+}
+
+macro_rules! tuples {
+    ($(($($name:ident),*);)*) => {
+        $(
+            impl<R, $($name),*> Persist<R> for ($($name,)*)
+            where
+                $($name: Persist<R>,)*
+            {
+                #[allow(non_snake_case)]
+                fn persist(&self, repo: &mut R) {
+                    let ($($name,)*) = self;
+                    $($name.persist(repo);)*
+                }
+            }
+        )*
+    };
+}
+
+tuples! {
+    (A);
+    (A, B);
+    (A, B, C);
+    (A, B, C, D);
+    (A, B, C, D, E);
+    (A, B, C, D, E, F);
+    (A, B, C, D, E, F, G);
+    (A, B, C, D, E, F, G, H);
+}
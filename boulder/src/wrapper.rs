@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// A type which can be constructed by wrapping a single value.
+///
+/// Implementing this trait for a wrapper type lets builders and
+/// generators pass through it: for any type `Foo` which derives
+/// [`Buildable`](crate::Buildable) or
+/// [`Generatable`](crate::Generatable),
+/// `Wrapper<Foo>::builder()` and `Wrapper<Foo>::generator()` will
+/// build or generate a `Foo` and then wrap it. Wrappers nest, so
+/// `Wrapper<Option<Foo>>` works too.
+///
+/// The standard library wrappers ([`Option`], [`Box`], [`Rc`],
+/// [`Arc`], [`Mutex`], [`RwLock`], [`Cell`], [`RefCell`],
+/// [`OnceCell`], `Cow<'static, T>` and `Pin<Box<T>>`) are all
+/// implemented in terms of this trait. With the `persian-rug`
+/// feature enabled, wrappers also pass through
+/// `BuildableWithPersianRug` and `GeneratableWithPersianRug`.
+///
+/// Example
+/// ```rust
+/// use boulder::{Buildable, Builder, BoulderWrapper, Generatable, Generator};
+///
+/// struct Tracked<T> {
+///     value: T,
+///     revision: u32,
+/// }
+///
+/// impl<T> BoulderWrapper for Tracked<T> {
+///     type Inner = T;
+///     fn wrap(inner: T) -> Self {
+///         Tracked { value: inner, revision: 1 }
+///     }
+/// }
+///
+/// #[derive(Buildable, Generatable)]
+/// struct Foo {
+///     #[boulder(default=5, generator=boulder::Inc(1))]
+///     a: i32,
+/// }
+///
+/// let t = Tracked::<Foo>::builder().a(7).build();
+/// assert_eq!(t.value.a, 7);
+/// assert_eq!(t.revision, 1);
+///
+/// let mut g = Tracked::<Option<Foo>>::generator();
+/// assert_eq!(g.generate().value.map(|f| f.a), Some(1));
+/// assert_eq!(g.generate().value.map(|f| f.a), Some(2));
+/// ```
+pub trait BoulderWrapper {
+    /// The type being wrapped.
+    type Inner;
+    /// Wrap a freshly built or generated value.
+    fn wrap(inner: Self::Inner) -> Self;
+}
+
+impl<T> BoulderWrapper for Option<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Option::Some(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Box<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Box::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Pin<Box<T>> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Box::pin(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Rc<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Rc::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Arc<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Arc::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Mutex<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Mutex::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for RwLock<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        RwLock::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Cell<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Cell::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for RefCell<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        RefCell::new(inner)
+    }
+}
+
+impl<T> BoulderWrapper for OnceCell<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        OnceCell::from(inner)
+    }
+}
+
+impl<T> BoulderWrapper for Cow<'static, T>
+where
+    T: Clone + 'static,
+{
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Cow::Owned(inner)
+    }
+}
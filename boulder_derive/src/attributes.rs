@@ -1,3 +1,4 @@
+use proc_macro2 as pm2;
 use std::collections::BTreeMap;
 
 pub struct AttributeValue {
@@ -14,6 +15,7 @@ impl syn::parse::Parse for AttributeValue {
     }
 }
 
+#[allow(dead_code)]
 pub enum AttributeItem {
     Buildable {
         buildable: syn::Ident,
@@ -23,6 +25,14 @@ pub enum AttributeItem {
         default: syn::Ident,
         expr: syn::Expr,
     },
+    DefaultFrom {
+        default_from: syn::Ident,
+        expr: syn::Expr,
+    },
+    DefaultAsync {
+        default_async: syn::Ident,
+        expr: syn::Expr,
+    },
     Sequence {
         sequence: syn::Ident,
         expr: syn::Expr,
@@ -39,6 +49,14 @@ pub enum AttributeItem {
         sequence_generator: syn::Ident,
         expr: syn::Expr,
     },
+    Recursive {
+        recursive: syn::Ident,
+        recursion: Recursion,
+    },
+    Setter {
+        setter: syn::Ident,
+        options: SetterOptions,
+    },
 
     BuildableWithPersianRug {
         buildable_with_persian_rug: syn::Ident,
@@ -103,6 +121,22 @@ impl syn::parse::Parse for AttributeItem {
                     expr: value,
                 })
             }
+            "default_from" => {
+                let _: syn::Token![=] = input.parse()?;
+                let value: syn::Expr = input.parse()?;
+                Ok(AttributeItem::DefaultFrom {
+                    default_from: attr,
+                    expr: value,
+                })
+            }
+            "default_async" => {
+                let _: syn::Token![=] = input.parse()?;
+                let value: syn::Expr = input.parse()?;
+                Ok(AttributeItem::DefaultAsync {
+                    default_async: attr,
+                    expr: value,
+                })
+            }
             "default_with_persian_rug" => {
                 let _: syn::Token![=] = input.parse()?;
                 let value: syn::Expr = input.parse()?;
@@ -190,6 +224,46 @@ impl syn::parse::Parse for AttributeItem {
                     expr: value,
                 })
             }
+            "recursive" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                let punc =
+                    syn::punctuated::Punctuated::<AttributeValue, syn::Token![,]>::parse_terminated(
+                        &content,
+                    )?;
+                let mut max_depth = None;
+                let mut branching = None;
+                for item in punc {
+                    match item.name.to_string().as_str() {
+                        "max_depth" => max_depth = Some(item.value),
+                        "branching" => branching = Some(item.value),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                item.name,
+                                "unsupported recursive option",
+                            ))
+                        }
+                    }
+                }
+                let max_depth = max_depth.ok_or_else(|| {
+                    syn::Error::new_spanned(&attr, "recursive requires a max_depth")
+                })?;
+                Ok(AttributeItem::Recursive {
+                    recursive: attr,
+                    recursion: Recursion {
+                        max_depth,
+                        branching,
+                    },
+                })
+            }
+            "setter" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                Ok(AttributeItem::Setter {
+                    setter: attr,
+                    options: content.parse()?,
+                })
+            }
             "sequence_generator_with_persian_rug" => {
                 let _: syn::Token![=] = input.parse()?;
                 let value: syn::Expr = input.parse()?;
@@ -212,12 +286,17 @@ impl syn::parse::Parse for AttributeItem {
     }
 }
 
+#[allow(dead_code)]
 pub enum BuildType {
     Buildable(BTreeMap<syn::Ident, syn::Expr>),
     Value {
         expr: Box<syn::Expr>,
         ty: Option<Box<syn::Type>>,
     },
+    // A function of the transient values, from `default_from`.
+    DefaultFrom(Box<syn::Expr>),
+    // A future to await, from `default_async`.
+    DefaultAsync(Box<syn::Expr>),
     Default,
 }
 
@@ -228,6 +307,12 @@ pub struct BuilderData {
     pub sequence_needs_context: bool,
 }
 
+#[derive(Clone)]
+pub struct Recursion {
+    pub max_depth: syn::Expr,
+    pub branching: Option<syn::Expr>,
+}
+
 pub enum GeneratorType {
     Generator {
         expr: Box<syn::Expr>,
@@ -240,6 +325,7 @@ pub enum GeneratorType {
 pub struct GeneratorData {
     pub element: GeneratorType,
     pub sequence: Option<(syn::Expr, Option<syn::Type>)>,
+    pub recursive: Option<Recursion>,
     pub needs_context: bool,
     pub sequence_needs_context: bool,
 }
@@ -262,9 +348,11 @@ impl syn::parse::Parse for BuilderMeta {
         let mut gd = GeneratorData {
             element: GeneratorType::Default,
             sequence: None,
+            recursive: None,
             needs_context: false,
             sequence_needs_context: false,
         };
+        let mut deferred = None;
 
         for ref item in punc {
             match item {
@@ -279,6 +367,26 @@ impl syn::parse::Parse for BuilderMeta {
                         ty: None,
                     };
                 }
+                AttributeItem::DefaultFrom {
+                    default_from: ident,
+                    expr,
+                } => {
+                    bd.element = BuildType::DefaultFrom(Box::new(expr.clone()));
+                    deferred = Some(ident.clone());
+                }
+                AttributeItem::DefaultAsync {
+                    default_async: ident,
+                    expr,
+                } => {
+                    if !cfg!(feature = "async") {
+                        return Err(syn::Error::new_spanned(
+                            ident,
+                            "default_async needs the \"async\" feature of boulder",
+                        ));
+                    }
+                    bd.element = BuildType::DefaultAsync(Box::new(expr.clone()));
+                    deferred = Some(ident.clone());
+                }
                 AttributeItem::Sequence { expr, .. } => {
                     bd.sequence = Some((expr.clone(), None));
                 }
@@ -296,6 +404,10 @@ impl syn::parse::Parse for BuilderMeta {
                 AttributeItem::SequenceGenerator { expr, .. } => {
                     gd.sequence = Some((expr.clone(), None));
                 }
+                AttributeItem::Recursive { recursion, .. } => {
+                    gd.recursive = Some(recursion.clone());
+                }
+                AttributeItem::Setter { .. } => {}
                 AttributeItem::BuildableWithPersianRug {
                     buildable_with_persian_rug: ident,
                     ..
@@ -331,6 +443,8 @@ impl syn::parse::Parse for BuilderMeta {
             }
         }
 
+        check_deferred(deferred, &bd)?;
+
         Ok(BuilderMeta {
             builder: bd,
             generator: gd,
@@ -356,9 +470,11 @@ impl syn::parse::Parse for BuilderMetaWithPersianRug {
         let mut gd = GeneratorData {
             element: GeneratorType::Default,
             sequence: None,
+            recursive: None,
             needs_context: false,
             sequence_needs_context: false,
         };
+        let mut deferred = None;
 
         for item in punc {
             match item {
@@ -373,6 +489,13 @@ impl syn::parse::Parse for BuilderMetaWithPersianRug {
                         ty: None,
                     };
                 }
+                AttributeItem::DefaultFrom {
+                    default_from: ident,
+                    expr,
+                } => {
+                    bd.element = BuildType::DefaultFrom(Box::new(expr.clone()));
+                    deferred = Some(ident.clone());
+                }
                 AttributeItem::Sequence { expr, .. } => {
                     bd.sequence = Some((expr.clone(), None));
                 }
@@ -390,6 +513,19 @@ impl syn::parse::Parse for BuilderMetaWithPersianRug {
                 AttributeItem::SequenceGenerator { expr, .. } => {
                     gd.sequence = Some((expr.clone(), None));
                 }
+                AttributeItem::Setter { .. } => {}
+                AttributeItem::Recursive {
+                    recursive: ident, ..
+                }
+                | AttributeItem::DefaultAsync {
+                    default_async: ident,
+                    ..
+                } => {
+                    return Err(syn::Error::new_spanned(
+                        &ident,
+                        format!("Cannot use {} inside a persian-rug enabled derive.", ident),
+                    ));
+                }
 
                 AttributeItem::BuildableWithPersianRug {
                     initializers: map, ..
@@ -428,9 +564,836 @@ impl syn::parse::Parse for BuilderMetaWithPersianRug {
             }
         }
 
+        check_deferred(deferred, &bd)?;
+
         Ok(BuilderMetaWithPersianRug {
             builder: bd,
             generator: gd,
         })
     }
 }
+
+/// Items built for a sequence are built synchronously, with no
+/// access to the transient values, so neither `default_from` nor
+/// `default_async` can describe them.
+fn check_deferred(deferred: Option<syn::Ident>, bd: &BuilderData) -> syn::Result<()> {
+    match deferred {
+        Some(ident) if bd.sequence.is_some() => Err(syn::Error::new_spanned(
+            &ident,
+            format!("{} cannot be combined with sequence", ident),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A path given either bare or as a string, as in serde.
+fn parse_path(input: syn::parse::ParseStream<'_>) -> syn::Result<syn::Path> {
+    if input.peek(syn::LitStr) {
+        let lit: syn::LitStr = input.parse()?;
+        lit.parse()
+    } else {
+        input.parse()
+    }
+}
+
+#[allow(dead_code)]
+pub enum TypeAttributeItem {
+    Remote {
+        remote: syn::Ident,
+        path: syn::Path,
+    },
+    Constructor {
+        constructor: syn::Ident,
+        path: syn::Path,
+    },
+    Auto {
+        auto: syn::Ident,
+    },
+    Preset {
+        preset: syn::Ident,
+        value: Preset,
+    },
+    Setter {
+        setter: syn::Ident,
+        options: SetterOptions,
+    },
+    Transient {
+        transient: syn::Ident,
+        values: syn::punctuated::Punctuated<Transient, syn::Token![,]>,
+    },
+    AfterBuild {
+        after_build: syn::Ident,
+        hook: syn::Expr,
+    },
+    AfterInsertWithPersianRug {
+        after_insert: syn::Ident,
+        hook: syn::Expr,
+    },
+    Patch {
+        patch: syn::Ident,
+        value: Patch,
+    },
+    // Handled by the persian-rug derives.
+    PersianRug {
+        persian_rug: syn::Ident,
+    },
+}
+
+impl syn::parse::Parse for TypeAttributeItem {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let attr: syn::Ident = input.parse()?;
+        match attr.to_string().as_str() {
+            "remote" => {
+                let _: syn::Token![=] = input.parse()?;
+                Ok(TypeAttributeItem::Remote {
+                    remote: attr,
+                    path: parse_path(input)?,
+                })
+            }
+            "constructor" => {
+                let _: syn::Token![=] = input.parse()?;
+                Ok(TypeAttributeItem::Constructor {
+                    constructor: attr,
+                    path: parse_path(input)?,
+                })
+            }
+            "auto" => Ok(TypeAttributeItem::Auto { auto: attr }),
+            "preset" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                Ok(TypeAttributeItem::Preset {
+                    preset: attr,
+                    value: content.parse()?,
+                })
+            }
+            "setter" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                Ok(TypeAttributeItem::Setter {
+                    setter: attr,
+                    options: content.parse()?,
+                })
+            }
+            "transient" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                Ok(TypeAttributeItem::Transient {
+                    transient: attr,
+                    values: syn::punctuated::Punctuated::parse_terminated(&content)?,
+                })
+            }
+            "after_build" => {
+                let _: syn::Token![=] = input.parse()?;
+                Ok(TypeAttributeItem::AfterBuild {
+                    after_build: attr,
+                    hook: input.parse()?,
+                })
+            }
+            "after_insert_with_persian_rug" => {
+                let _: syn::Token![=] = input.parse()?;
+                Ok(TypeAttributeItem::AfterInsertWithPersianRug {
+                    after_insert: attr,
+                    hook: input.parse()?,
+                })
+            }
+            "patch" => {
+                let value = if input.peek(syn::token::Paren) {
+                    let content;
+                    let _: syn::token::Paren = syn::parenthesized!(content in input);
+                    content.parse()?
+                } else {
+                    Patch::default()
+                };
+                Ok(TypeAttributeItem::Patch { patch: attr, value })
+            }
+            "persian_rug" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                let _: pm2::TokenStream = content.parse()?;
+                Ok(TypeAttributeItem::PersianRug { persian_rug: attr })
+            }
+            _ => Err(syn::Error::new_spanned(
+                attr,
+                "unsupported boulder attribute",
+            )),
+        }
+    }
+}
+
+/// A foreign type which a mirror struct stands in for.
+pub struct Remote {
+    pub path: syn::Path,
+    pub constructor: Option<syn::Path>,
+}
+
+impl Remote {
+    /// Construct the remote type from local variables named after
+    /// the mirror's fields.
+    pub fn construct(&self, fields: &[syn::Ident]) -> pm2::TokenStream {
+        let path = &self.path;
+        match &self.constructor {
+            Some(constructor) => quote::quote! {
+                #constructor(#(#fields),*)
+            },
+            None => quote::quote! {
+                #path { #(#fields),* }
+            },
+        }
+    }
+}
+
+/// A named set of field values, applied by a method of the same name
+/// on the derived builder or generator.
+pub struct Preset {
+    pub name: syn::Ident,
+    pub values: Vec<(syn::Ident, syn::Expr)>,
+}
+
+impl syn::parse::Parse for Preset {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let name: syn::Ident = input.parse()?;
+        let mut values = Vec::new();
+        if input.peek(syn::Token![,]) {
+            let _: syn::Token![,] = input.parse()?;
+            let punc =
+                syn::punctuated::Punctuated::<AttributeValue, syn::Token![,]>::parse_terminated(
+                    input,
+                )?;
+            values.extend(punc.into_iter().map(|v| (v.name, v.value)));
+        }
+        Ok(Self { name, values })
+    }
+}
+
+impl Preset {
+    /// Chain a call to the setter for each field in the preset onto
+    /// `receiver`, where `call` gives the method call from the field
+    /// and the preset value.
+    pub fn apply<F>(
+        &self,
+        fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+        receiver: pm2::TokenStream,
+        call: F,
+    ) -> syn::Result<pm2::TokenStream>
+    where
+        F: Fn(&syn::Field, &syn::Expr) -> syn::Result<pm2::TokenStream>,
+    {
+        let mut res = receiver;
+        for (name, value) in &self.values {
+            let field = fields
+                .iter()
+                .find(|f| f.ident.as_ref() == Some(name))
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(name, format!("no field named `{}`", name))
+                })?;
+            let call = call(field, value)?;
+            res = quote::quote! {
+                #res #call
+            };
+        }
+        Ok(res)
+    }
+}
+
+/// A struct with every field of the derived type wrapped in
+/// `Option`, from `#[boulder(patch)]`, which the derived builder can
+/// apply.
+#[derive(Default)]
+pub struct Patch {
+    pub derives: Vec<syn::Path>,
+}
+
+impl syn::parse::Parse for Patch {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let name: syn::Ident = input.parse()?;
+        if name != "derive" {
+            return Err(syn::Error::new_spanned(name, "unsupported patch option"));
+        }
+        let content;
+        let _: syn::token::Paren = syn::parenthesized!(content in input);
+        let derives =
+            syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated(&content)?;
+        Ok(Self {
+            derives: derives.into_iter().collect(),
+        })
+    }
+}
+
+impl Patch {
+    /// The name of the patch struct for the type `ident`.
+    pub fn ident(ident: &syn::Ident) -> syn::Ident {
+        quote::format_ident!("{}Patch", ident)
+    }
+
+    /// The patch struct for the type `ident`, whose fields are
+    /// `fields`.
+    pub fn definition(
+        &self,
+        ident: &syn::Ident,
+        vis: &syn::Visibility,
+        generics: &syn::Generics,
+        fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    ) -> pm2::TokenStream {
+        let patch = Self::ident(ident);
+        let derives = &self.derives;
+        let (impl_generics, ty_generics, wc) = generics.split_for_impl();
+        let params = &generics.params;
+        let attrs = fields
+            .iter()
+            .map(|f| forwarded_attributes(f, &["doc", "cfg"]));
+        let cfgs = fields.iter().map(|f| forwarded_attributes(f, &["cfg"]));
+        let visibilities = fields.iter().map(|f| &f.vis);
+        let names = fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+        let types = fields.iter().map(|f| &f.ty);
+        let doc = format!(
+            "Optional overrides for the fields of [`{}`], for its builder's `apply` method.",
+            ident
+        );
+        quote::quote! {
+            #[doc = #doc]
+            #[derive(#(#derives),*)]
+            #vis struct #patch <#params> #wc {
+                #(
+                    #attrs
+                    #visibilities #names: ::core::option::Option<#types>,
+                )*
+            }
+
+            #[automatically_derived]
+            impl #impl_generics ::core::default::Default for #patch #ty_generics #wc {
+                fn default() -> Self {
+                    Self {
+                        #(#cfgs #names: ::core::option::Option::None,)*
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct TypeMeta {
+    pub remote: Option<Remote>,
+    pub auto: bool,
+    pub presets: Vec<Preset>,
+    pub setter: SetterOptions,
+    pub transients: Transients,
+    pub after_build: Vec<syn::Expr>,
+    // Only the persian-rug derives have anywhere to insert into.
+    #[cfg_attr(not(feature = "persian-rug"), allow(dead_code))]
+    pub after_insert: Vec<syn::Expr>,
+    // Only `Buildable` has a patch.
+    pub patch: Option<Patch>,
+}
+
+impl TypeMeta {
+    pub fn from_attributes(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut remote = None;
+        let mut constructor = None;
+        let mut auto = false;
+        let mut presets = Vec::new();
+        let mut setter = SetterOptions::default();
+        let mut transients = Vec::new();
+        let mut after_build = Vec::new();
+        let mut after_insert = Vec::new();
+        let mut patch = None;
+        for attr in attrs {
+            if attr.path.is_ident("boulder") {
+                let items = attr.parse_args_with(
+                    syn::punctuated::Punctuated::<TypeAttributeItem, syn::Token![,]>::parse_terminated,
+                )?;
+                for item in items {
+                    match item {
+                        TypeAttributeItem::Remote { path, .. } => remote = Some(path),
+                        TypeAttributeItem::Constructor {
+                            constructor: ident,
+                            path,
+                        } => constructor = Some((ident, path)),
+                        TypeAttributeItem::Auto { .. } => auto = true,
+                        TypeAttributeItem::Preset { value, .. } => presets.push(value),
+                        TypeAttributeItem::Setter { options, .. } => setter = options.or(&setter),
+                        TypeAttributeItem::Transient { values, .. } => transients.extend(values),
+                        TypeAttributeItem::AfterBuild { hook, .. } => after_build.push(hook),
+                        TypeAttributeItem::AfterInsertWithPersianRug { hook, .. } => {
+                            after_insert.push(hook)
+                        }
+                        TypeAttributeItem::Patch { value, .. } => patch = Some(value),
+                        TypeAttributeItem::PersianRug { .. } => {}
+                    }
+                }
+            }
+        }
+        let remote = match (remote, constructor) {
+            (Some(path), constructor) => Some(Remote {
+                path,
+                constructor: constructor.map(|(_, path)| path),
+            }),
+            (None, Some((ident, _))) => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "constructor can only be used together with remote",
+                ))
+            }
+            (None, None) => None,
+        };
+        Ok(TypeMeta {
+            remote,
+            auto,
+            presets,
+            setter,
+            transients: Transients(transients),
+            after_build,
+            after_insert,
+            patch,
+        })
+    }
+}
+
+/// Run the struct's `after_build` hooks, and then any added to the
+/// builder or generator in `hooks`, on `value`, a place of type `ty`.
+pub fn after_build(
+    statics: &[syn::Expr],
+    ty: pm2::TokenStream,
+    value: pm2::TokenStream,
+    hooks: pm2::TokenStream,
+) -> pm2::TokenStream {
+    quote::quote! {
+        #(
+            {
+                let hook: fn(&mut #ty) = #statics;
+                hook(&mut #value);
+            }
+        )*
+        for hook in #hooks {
+            hook(&mut #value);
+        }
+    }
+}
+
+/// A value which the derived builder and generator accept, but which
+/// isn't a field, from `#[boulder(transient(name: Type = default))]`.
+pub struct Transient {
+    pub name: syn::Ident,
+    pub ty: syn::Type,
+    pub default: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for Transient {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let _: syn::Token![:] = input.parse()?;
+        let ty = input.parse()?;
+        let default = if input.peek(syn::Token![=]) {
+            let _: syn::Token![=] = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { name, ty, default })
+    }
+}
+
+/// All the transient values declared on a struct.
+pub struct Transients(Vec<Transient>);
+
+impl Transients {
+    /// The struct holding the transient values, which `default_from`
+    /// functions receive by reference.
+    pub fn definition(&self) -> pm2::TokenStream {
+        let names = self.0.iter().map(|t| &t.name);
+        let types = self.0.iter().map(|t| &t.ty);
+        let defaults = self.0.iter().map(|t| match &t.default {
+            Some(default) => quote::quote! { #default },
+            None => quote::quote! { ::core::default::Default::default() },
+        });
+        let names2 = names.clone();
+        let names3 = names.clone();
+        let types2 = types.clone();
+        quote::quote! {
+            #[allow(dead_code)]
+            struct BoulderTransients {
+                #(#names: #types,)*
+            }
+
+            impl BoulderTransients {
+                fn new() -> Self {
+                    Self {
+                        #(#names2: #defaults,)*
+                    }
+                }
+            }
+
+            impl ::core::clone::Clone for BoulderTransients
+            where
+                #(for<'boulder_clone> #types2: ::core::clone::Clone,)*
+            {
+                fn clone(&self) -> Self {
+                    Self {
+                        #(#names3: ::core::clone::Clone::clone(&self.#names3),)*
+                    }
+                }
+            }
+        }
+    }
+
+    /// The setters for the transient values, on a builder or
+    /// generator with a `_boulder_transient` field. If `track` is
+    /// set, the setters also record their names in the builder's
+    /// `_boulder_set`, for `merge`.
+    pub fn methods(
+        &self,
+        fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+        track: bool,
+    ) -> syn::Result<pm2::TokenStream> {
+        let mut res = pm2::TokenStream::new();
+        for Transient { name, ty, .. } in &self.0 {
+            if fields.iter().any(|f| f.ident.as_ref() == Some(name)) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!("transient `{}` has the same name as a field", name),
+                ));
+            }
+            let mark = if track {
+                let key = name.to_string();
+                quote::quote! { self._boulder_set.insert(#key); }
+            } else {
+                pm2::TokenStream::new()
+            };
+            res.extend(quote::quote! {
+                pub fn #name<BoulderFunctionParam>(mut self, value: BoulderFunctionParam) -> Self
+                where
+                    BoulderFunctionParam: ::core::convert::Into<#ty>
+                {
+                    self._boulder_transient.#name = ::core::convert::Into::into(value);
+                    #mark
+                    self
+                }
+            });
+        }
+        Ok(res)
+    }
+
+    /// Copy the transient values which were set on the builder
+    /// `other` into `self`, for `merge`.
+    pub fn merge(&self) -> pm2::TokenStream {
+        let names = self.0.iter().map(|t| &t.name);
+        let keys = self.0.iter().map(|t| t.name.to_string());
+        quote::quote! {
+            #(
+                if other._boulder_set.contains(#keys) {
+                    self._boulder_transient.#names = other._boulder_transient.#names;
+                    self._boulder_set.insert(#keys);
+                }
+            )*
+        }
+    }
+}
+
+/// Evaluate a `default_from` function for a field of type `ty`, given
+/// a reference to the transient values.
+pub fn default_from(
+    expr: &syn::Expr,
+    ty: &syn::Type,
+    transients: pm2::TokenStream,
+) -> pm2::TokenStream {
+    quote::quote! {
+        {
+            let default_from: fn(&BoulderTransients) -> #ty = #expr;
+            default_from(#transients)
+        }
+    }
+}
+
+/// How the derived builder's setter for a field takes its value,
+/// from `#[boulder(setter(...))]` on the field or on the struct.
+#[derive(Clone, Default)]
+pub struct SetterOptions {
+    strip_option: Option<(syn::Ident, bool)>,
+    into: Option<bool>,
+    prefix: Option<String>,
+}
+
+impl syn::parse::Parse for SetterOptions {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let mut res = Self::default();
+        while !input.is_empty() {
+            let name: syn::Ident = input.parse()?;
+            let value: Option<syn::Lit> = if input.peek(syn::Token![=]) {
+                let _: syn::Token![=] = input.parse()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            match (name.to_string().as_str(), value) {
+                ("strip_option", None) => res.strip_option = Some((name, true)),
+                ("strip_option", Some(syn::Lit::Bool(b))) => {
+                    res.strip_option = Some((name, b.value))
+                }
+                ("into", None) => res.into = Some(true),
+                ("into", Some(syn::Lit::Bool(b))) => res.into = Some(b.value),
+                ("prefix", Some(syn::Lit::Str(s))) => res.prefix = Some(s.value()),
+                ("strip_option" | "into", Some(lit)) => {
+                    return Err(syn::Error::new_spanned(lit, "expected `true` or `false`"))
+                }
+                ("prefix", _) => {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        "prefix requires a string, as in `prefix = \"with_\"`",
+                    ))
+                }
+                _ => return Err(syn::Error::new_spanned(name, "unsupported setter option")),
+            }
+            if !input.is_empty() {
+                let _: syn::Token![,] = input.parse()?;
+            }
+        }
+        Ok(res)
+    }
+}
+
+impl SetterOptions {
+    /// Use these options, falling back to `other` for any that
+    /// aren't given.
+    pub fn or(self, other: &SetterOptions) -> SetterOptions {
+        SetterOptions {
+            strip_option: self.strip_option.or_else(|| other.strip_option.clone()),
+            into: self.into.or(other.into),
+            prefix: self.prefix.or_else(|| other.prefix.clone()),
+        }
+    }
+
+    /// Resolve the setter for `field` from its attributes. Options
+    /// given on the struct are passed in `defaults`; `strip_option`
+    /// there only applies to fields of `Option` type.
+    pub fn setter(defaults: &SetterOptions, field: &syn::Field) -> syn::Result<Setter> {
+        use syn::ext::IdentExt;
+
+        let mut options = SetterOptions::default();
+        for attr in &field.attrs {
+            if attr.path.is_ident("boulder") {
+                let items = attr.parse_args_with(
+                    syn::punctuated::Punctuated::<AttributeItem, syn::Token![,]>::parse_terminated,
+                )?;
+                for item in items {
+                    if let AttributeItem::Setter { options: o, .. } = item {
+                        options = options.or(&o);
+                    }
+                }
+            }
+        }
+
+        let fieldid = field.ident.as_ref().unwrap();
+        let inner = option_inner(&field.ty);
+        let strip_option = match (&options.strip_option, inner) {
+            (Some((_, true)), Some(_)) => true,
+            (Some((ident, true)), None) => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "strip_option can only be used on an Option field",
+                ))
+            }
+            (Some((_, false)), _) => false,
+            (None, inner) => inner.is_some() && matches!(defaults.strip_option, Some((_, true))),
+        };
+        let options = options.or(defaults);
+        Ok(Setter {
+            name: match &options.prefix {
+                Some(prefix) => quote::format_ident!("{}{}", prefix, fieldid.unraw()),
+                None => fieldid.clone(),
+            },
+            unset: inner
+                .is_some()
+                .then(|| quote::format_ident!("unset_{}", fieldid.unraw())),
+            value_type: match inner {
+                Some(inner) if strip_option => inner.clone(),
+                _ => field.ty.clone(),
+            },
+            strip_option,
+            into: options.into.unwrap_or(true),
+            attrs: forwarded_attributes(field, &["doc", "cfg"]),
+            cfg: forwarded_attributes(field, &["cfg"]),
+        })
+    }
+}
+
+/// A resolved setter for one field of a derived builder.
+pub struct Setter {
+    pub name: syn::Ident,
+    pub unset: Option<syn::Ident>,
+    value_type: syn::Type,
+    strip_option: bool,
+    into: bool,
+    attrs: pm2::TokenStream,
+    pub cfg: pm2::TokenStream,
+}
+
+impl Setter {
+    /// The setter method itself, plus `unset_<field>` for `Option`
+    /// fields, where `store` turns an expression of the field type
+    /// into the statements which record it in the builder.
+    pub fn methods<F>(&self, store: F) -> pm2::TokenStream
+    where
+        F: Fn(pm2::TokenStream) -> pm2::TokenStream,
+    {
+        let Self {
+            name,
+            value_type,
+            attrs,
+            cfg,
+            ..
+        } = self;
+        let value = if self.into {
+            quote::quote! { ::core::convert::Into::into(value) }
+        } else {
+            quote::quote! { value }
+        };
+        let value = if self.strip_option {
+            quote::quote! { ::core::option::Option::Some(#value) }
+        } else {
+            value
+        };
+        let store_value = store(value);
+        let mut res = if self.into {
+            quote::quote! {
+                #attrs
+                pub fn #name<BoulderFunctionParam>(mut self, value: BoulderFunctionParam) -> Self
+                where
+                    BoulderFunctionParam: ::core::convert::Into<#value_type>
+                {
+                    #store_value
+                    self
+                }
+            }
+        } else {
+            quote::quote! {
+                #attrs
+                pub fn #name(mut self, value: #value_type) -> Self {
+                    #store_value
+                    self
+                }
+            }
+        };
+        if let Some(unset) = &self.unset {
+            let store_none = store(quote::quote! { ::core::option::Option::None });
+            res.extend(quote::quote! {
+                #cfg
+                pub fn #unset(mut self) -> Self {
+                    #store_none
+                    self
+                }
+            });
+        }
+        res
+    }
+
+    /// A preset value converted to the field type, as this setter
+    /// would convert it.
+    pub fn preset_value(&self, value: &syn::Expr) -> pm2::TokenStream {
+        let value_type = &self.value_type;
+        let value = if self.into {
+            quote::quote! { ::core::convert::Into::<#value_type>::into(#value) }
+        } else {
+            quote::quote! { ::core::convert::identity::<#value_type>(#value) }
+        };
+        if self.strip_option {
+            quote::quote! { ::core::option::Option::Some(#value) }
+        } else {
+            value
+        }
+    }
+
+    /// The call to this setter with a preset value.
+    pub fn call(&self, value: &syn::Expr) -> pm2::TokenStream {
+        let name = &self.name;
+        if self.into {
+            quote::quote! { .#name(#value) }
+        } else {
+            let value_type = &self.value_type;
+            quote::quote! { .#name(::core::convert::identity::<#value_type>(#value)) }
+        }
+    }
+}
+
+/// The attributes on `field` with the given names, to be repeated on
+/// the code generated for it.
+pub fn forwarded_attributes(field: &syn::Field, names: &[&str]) -> pm2::TokenStream {
+    let attrs = field
+        .attrs
+        .iter()
+        .filter(|attr| names.iter().any(|name| attr.path.is_ident(name)));
+    quote::quote! { #(#attrs)* }
+}
+
+/// The type wrapped by `ty`, if it is an `Option`.
+pub fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args)
+            if segment.ident == "Option" && args.args.len() == 1 =>
+        {
+            match args.args.first()? {
+                syn::GenericArgument::Type(inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Collect the items from `iter` into a field of type `ty`.
+///
+/// Arrays have no [`FromIterator`] implementation, so they are
+/// filled through a helper which checks the length instead.
+pub fn collect_sequence(ty: &syn::Type, iter: pm2::TokenStream) -> pm2::TokenStream {
+    if let syn::Type::Array(_) = ty {
+        quote::quote! {
+            ::boulder::guts::collect_array(#iter)
+        }
+    } else {
+        quote::quote! {
+            (#iter).collect()
+        }
+    }
+}
+
+/// A varying generator for a field of type `ty`, for use by
+/// `#[boulder(auto)]`, or `None` if the type isn't recognised.
+pub fn auto_generator(field: &syn::Ident, ty: &syn::Type) -> Option<syn::Expr> {
+    use syn::ext::IdentExt;
+
+    let segment = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    match (segment.ident.to_string().as_str(), &segment.arguments) {
+        (
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+            | "isize" | "f32" | "f64",
+            syn::PathArguments::None,
+        ) => Some(syn::parse_quote! {
+            ::boulder::Inc(<#ty as ::core::default::Default>::default())
+        }),
+        ("String", syn::PathArguments::None) => {
+            let pattern =
+                syn::LitStr::new(&format!("{}-{{}}", field.unraw()), pm2::Span::call_site());
+            Some(syn::parse_quote! {
+                ::boulder::Pattern!(#pattern, ::boulder::Inc(0usize))
+            })
+        }
+        ("bool", syn::PathArguments::None) => Some(syn::parse_quote! {
+            ::boulder::Cycle::new([false, true].into_iter())
+        }),
+        ("Option", syn::PathArguments::AngleBracketed(args)) if args.args.len() == 1 => {
+            let inner = match args.args.first()? {
+                syn::GenericArgument::Type(inner) => auto_generator(field, inner)?,
+                _ => return None,
+            };
+            Some(syn::parse_quote! {
+                ::boulder::Maybe::new(#inner, ::boulder::Frequency::Every(2))
+            })
+        }
+        _ => None,
+    }
+}
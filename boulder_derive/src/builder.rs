@@ -1,4 +1,5 @@
 use proc_macro2 as pm2;
+use syn::ext::IdentExt;
 
 use crate::attributes::*;
 
@@ -8,24 +9,63 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
         data,
         generics: full_generics,
         vis,
+        attrs,
         ..
     } = input;
 
+    let TypeMeta {
+        remote,
+        presets,
+        setter: setter_defaults,
+        transients,
+        after_build: after_build_hooks,
+        patch,
+        ..
+    } = match TypeMeta::from_attributes(&attrs) {
+        Ok(meta) => meta,
+        Err(e) => return e.to_compile_error(),
+    };
+
     let (generics, ty_generics, wc) = full_generics.split_for_impl();
 
     let mut body = pm2::TokenStream::new();
     let mut methods = pm2::TokenStream::new();
     let mut make_body = pm2::TokenStream::new();
+    let mut async_make_body = pm2::TokenStream::new();
+    // Whether any field has a default which must be awaited, in which
+    // case the builder only implements `AsyncBuilder`.
+    let mut asynchronous = false;
+    let mut change_type_body = pm2::TokenStream::new();
     let mut defaults = pm2::TokenStream::new();
+    let mut fieldids = Vec::new();
+    let mut fieldtypes = Vec::new();
+    let mut from_body = pm2::TokenStream::new();
+    let mut setters = std::collections::HashMap::new();
+    let mut field_patterns = Vec::new();
+    let mut clone_body = pm2::TokenStream::new();
+    let mut persist_fields = Vec::new();
+    let mut persist_types = Vec::new();
+    let mut from_set = pm2::TokenStream::new();
+    let mut apply_body = pm2::TokenStream::new();
+    let mut merge_body = pm2::TokenStream::new();
+    let mut builder_clone_body = pm2::TokenStream::new();
+    let mut vary_body = pm2::TokenStream::new();
+    let mut vary_patterns = Vec::new();
+    let mut patch_definition = pm2::TokenStream::new();
 
     if let syn::Data::Struct(s) = data {
         if let syn::Fields::Named(syn::FieldsNamed { named, .. }) = s.fields {
             for field in named.iter() {
                 let fieldid = field.ident.as_ref().unwrap();
                 let fieldtype = &field.ty;
+                fieldids.push(fieldid.clone());
+                fieldtypes.push(fieldtype.clone());
                 let mut builder = BuildType::Default;
                 let mut generator = GeneratorType::Default;
                 let mut sequence = None;
+                let mut nested_builder = None;
+                let mut sequence_init = None;
+                let mut deferred_default = None;
 
                 for attr in field.attrs.iter() {
                     if attr.path.is_ident("boulder") {
@@ -47,6 +87,32 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
                     }
                 }
 
+                let setter = match SetterOptions::setter(&setter_defaults, field) {
+                    Ok(setter) => setter,
+                    Err(e) => return e.to_compile_error(),
+                };
+                let cfg = &setter.cfg;
+                // The builder stores the field's type, so lints allowed
+                // on the field must be allowed there too.
+                let lints = forwarded_attributes(field, &["allow"]);
+                // Setting a field records its name, so that `merge` can
+                // tell it apart from a default.
+                let key = fieldid.unraw().to_string();
+                let mark = quote::quote! {
+                    self._boulder_set.insert(#key);
+                };
+                from_set.extend(quote::quote! {
+                    #cfg set.insert(#key);
+                });
+
+                let buildable_items = matches!(builder, BuildType::Buildable(_));
+                // Fields which may be compiled out can't appear in the
+                // bounds, so they are not stored.
+                if buildable_items && cfg.is_empty() {
+                    persist_fields.push(fieldid.clone());
+                    persist_types.push(fieldtype.clone());
+                }
+
                 if let Some((sequence, _)) = sequence {
                     let mut gen_init = pm2::TokenStream::new();
                     match generator {
@@ -91,6 +157,9 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
                                         (#value).into(),
                                     });
                                 }
+                                BuildType::DefaultFrom(_) | BuildType::DefaultAsync(_) => {
+                                    unreachable!("deferred defaults are rejected for sequences")
+                                }
                                 BuildType::Default => {
                                     static_value.extend(quote::quote! {
                                         Default::default()
@@ -102,9 +171,16 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
                             });
                         }
                     }
+                    let value = collect_sequence(
+                        fieldtype,
+                        quote::quote! {
+                            ::boulder::GeneratorIterator::new(#gen_init).take(#sequence)
+                        },
+                    );
                     defaults.extend(quote::quote! {
-                        #fieldid: ::boulder::GeneratorIterator::new(#gen_init).take(#sequence).collect(),
-                    })
+                        #cfg #fieldid: #value,
+                    });
+                    sequence_init = Some(gen_init);
                 } else {
                     match builder {
                         BuildType::Buildable(map) => {
@@ -119,32 +195,321 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
                                 });
                             }
                             defaults.extend(quote::quote! {
-                                #fieldid: <<#fieldtype as ::boulder::Buildable>::Builder as ::boulder::Builder>::build(#init),
+                                #cfg #fieldid: <<#fieldtype as ::boulder::Buildable>::Builder as ::boulder::Builder>::build(#init),
                             });
+                            nested_builder = Some(init);
                         }
                         BuildType::Value { expr: value, .. } => defaults.extend(quote::quote! {
-                            #fieldid: (#value).into(),
+                            #cfg #fieldid: (#value).into(),
                         }),
+                        deferred @ (BuildType::DefaultFrom(_) | BuildType::DefaultAsync(_)) => {
+                            defaults.extend(quote::quote! {
+                                #cfg #fieldid: ::core::option::Option::None,
+                            });
+                            deferred_default = Some(deferred);
+                        }
                         BuildType::Default => defaults.extend(quote::quote! {
-                            #fieldid: Default::default(),
+                            #cfg #fieldid: Default::default(),
                         }),
                     };
                 }
 
-                body.extend(quote::quote! {
-                    #fieldid: #fieldtype,
+                change_type_body.extend(quote::quote! {
+                    #cfg #fieldid: self.#fieldid,
+                });
+                builder_clone_body.extend(quote::quote! {
+                    #cfg #fieldid: ::core::clone::Clone::clone(&self.#fieldid),
+                });
+
+                if let Some(deferred) = deferred_default {
+                    // The default depends on the transient values, or
+                    // must be awaited, so it is only computed when the
+                    // builder is built.
+                    let (default, async_default) = match deferred {
+                        BuildType::DefaultAsync(expr) => {
+                            asynchronous = true;
+                            (
+                                None,
+                                quote::quote! {
+                                    {
+                                        let value: #fieldtype = (#expr).await;
+                                        value
+                                    }
+                                },
+                            )
+                        }
+                        BuildType::DefaultFrom(expr) => {
+                            let default = default_from(
+                                &expr,
+                                fieldtype,
+                                quote::quote! { &self._boulder_transient },
+                            );
+                            (Some(default.clone()), default)
+                        }
+                        _ => unreachable!("only deferred defaults are stored"),
+                    };
+                    body.extend(quote::quote! {
+                        #cfg #lints #fieldid: ::core::option::Option<#fieldtype>,
+                    });
+                    from_body.extend(quote::quote! {
+                        #cfg #fieldid: ::core::option::Option::Some(#fieldid),
+                    });
+                    let store = |value| {
+                        quote::quote! {
+                            self.#fieldid = ::core::option::Option::Some(#value);
+                            #mark
+                        }
+                    };
+                    methods.extend(setter.methods(store));
+                    apply_body.extend(patch_field(cfg, fieldid, store));
+                    merge_body.extend(quote::quote! {
+                        #cfg if other._boulder_set.contains(#key) {
+                            self.#fieldid = other.#fieldid;
+                            #mark
+                        }
+                    });
+                    if let Some(default) = default {
+                        make_body.extend(quote::quote! {
+                            #cfg #fieldid: match self.#fieldid {
+                                ::core::option::Option::Some(value) => value,
+                                ::core::option::Option::None => #default,
+                            },
+                        });
+                    }
+                    async_make_body.extend(quote::quote! {
+                        #cfg #fieldid: match self.#fieldid {
+                            ::core::option::Option::Some(value) => value,
+                            ::core::option::Option::None => #async_default,
+                        },
+                    });
+                } else if let Some(init) = nested_builder {
+                    body.extend(quote::quote! {
+                        #cfg #lints #fieldid: #fieldtype,
+                    });
+                    from_body.extend(quote::quote! {
+                        #cfg #fieldid,
+                    });
+                    // The customised nested builder is only built
+                    // when the outer builder is.
+                    let with_id = quote::format_ident!("_boulder_with_{}", fieldid.unraw());
+                    let with_method = quote::format_ident!("{}_with", fieldid.unraw());
+                    body.extend(quote::quote! {
+                        #cfg #with_id: ::core::option::Option<::std::sync::Arc<dyn Fn() -> #fieldtype + Send + Sync>>,
+                    });
+                    defaults.extend(quote::quote! {
+                        #cfg #with_id: ::core::option::Option::None,
+                    });
+                    change_type_body.extend(quote::quote! {
+                        #cfg #with_id: self.#with_id,
+                    });
+                    builder_clone_body.extend(quote::quote! {
+                        #cfg #with_id: ::core::clone::Clone::clone(&self.#with_id),
+                    });
+                    from_body.extend(quote::quote! {
+                        #cfg #with_id: ::core::option::Option::None,
+                    });
+                    let store = |value| {
+                        quote::quote! {
+                            self.#fieldid = #value;
+                            self.#with_id = ::core::option::Option::None;
+                            #mark
+                        }
+                    };
+                    methods.extend(setter.methods(store));
+                    apply_body.extend(patch_field(cfg, fieldid, store));
+                    merge_body.extend(quote::quote! {
+                        #cfg if other._boulder_set.contains(#key) {
+                            self.#fieldid = other.#fieldid;
+                            self.#with_id = other.#with_id;
+                            #mark
+                        }
+                    });
+                    vary_patterns.push(quote::quote! { #cfg #fieldid });
+                    vary_body.extend(quote::quote! {
+                        #cfg if !self._boulder_set.contains(#key) {
+                            builder.#fieldid = #fieldid;
+                        }
+                    });
+                    methods.extend(quote::quote! {
+                        #cfg
+                        pub fn #with_method<F>(mut self, f: F) -> Self
+                        where
+                            F: Fn(<#fieldtype as ::boulder::Buildable>::Builder) -> <#fieldtype as ::boulder::Buildable>::Builder + Send + Sync + 'static
+                        {
+                            self.#with_id = ::core::option::Option::Some(::std::sync::Arc::new(move || {
+                                <<#fieldtype as ::boulder::Buildable>::Builder as ::boulder::Builder>::build(f(#init))
+                            }));
+                            #mark
+                            self
+                        }
+                    });
+                    let value = quote::quote! {
+                        #cfg #fieldid: match self.#with_id {
+                            ::core::option::Option::Some(f) => f(),
+                            ::core::option::Option::None => self.#fieldid,
+                        },
+                    };
+                    make_body.extend(value.clone());
+                    async_make_body.extend(value);
+                } else {
+                    body.extend(quote::quote! {
+                        #cfg #lints #fieldid: #fieldtype,
+                    });
+                    from_body.extend(quote::quote! {
+                        #cfg #fieldid,
+                    });
+                    let store = |value| {
+                        quote::quote! {
+                            self.#fieldid = #value;
+                            #mark
+                        }
+                    };
+                    methods.extend(setter.methods(store));
+                    apply_body.extend(patch_field(cfg, fieldid, store));
+                    merge_body.extend(quote::quote! {
+                        #cfg if other._boulder_set.contains(#key) {
+                            self.#fieldid = other.#fieldid;
+                            #mark
+                        }
+                    });
+                    vary_patterns.push(quote::quote! { #cfg #fieldid });
+                    vary_body.extend(quote::quote! {
+                        #cfg if !self._boulder_set.contains(#key) {
+                            builder.#fieldid = #fieldid;
+                        }
+                    });
+                    let value = quote::quote! {
+                        #cfg #fieldid: self.#fieldid,
+                    };
+                    make_body.extend(value.clone());
+                    async_make_body.extend(value);
+                }
+
+                if let Some(gen_init) = sequence_init {
+                    let item = quote::quote! {
+                        <#fieldtype as ::std::iter::IntoIterator>::Item
+                    };
+                    // The bounds mentioning the field type are
+                    // higher-ranked, so that they are only checked when
+                    // these methods are used.
+                    if buildable_items {
+                        let at_method = quote::format_ident!("{}_at", fieldid.unraw());
+                        let field_name = fieldid.unraw().to_string();
+                        let collected = collect_sequence(fieldtype, quote::quote! { items });
+                        methods.extend(quote::quote! {
+                            #cfg
+                            pub fn #at_method<F>(mut self, index: usize, f: F) -> Self
+                            where
+                                F: FnOnce(<#item as ::boulder::Buildable>::Builder) -> <#item as ::boulder::Buildable>::Builder,
+                                for<'boulder_sequence> <#item as ::boulder::Buildable>::Builder: ::core::convert::From<#item>,
+                            {
+                                let mut f = ::core::option::Option::Some(f);
+                                let mut found = false;
+                                let items = ::std::iter::IntoIterator::into_iter(self.#fieldid)
+                                    .enumerate()
+                                    .map(|(i, item)| match f.take() {
+                                        ::core::option::Option::Some(f) if i == index => {
+                                            found = true;
+                                            <<#item as ::boulder::Buildable>::Builder as ::boulder::Builder>::build(f(::core::convert::From::from(item)))
+                                        }
+                                        other => {
+                                            f = other;
+                                            item
+                                        }
+                                    });
+                                self.#fieldid = #collected;
+                                if !found {
+                                    panic!("index {} is out of range for `{}`", index, #field_name);
+                                }
+                                #mark
+                                self
+                            }
+                        });
+                    }
+
+                    // Arrays have a fixed length, so they can't grow
+                    // or be regenerated at a different size.
+                    if !matches!(fieldtype, syn::Type::Array(_)) {
+                        let push_method = quote::format_ident!("push_{}", fieldid.unraw());
+                        let extend_method = quote::format_ident!("extend_{}", fieldid.unraw());
+                        let len_method = quote::format_ident!("{}_len", fieldid.unraw());
+                        let regenerated = collect_sequence(
+                            fieldtype,
+                            quote::quote! {
+                                ::boulder::GeneratorIterator::new(#gen_init).take(len)
+                            },
+                        );
+                        methods.extend(quote::quote! {
+                            #cfg
+                            pub fn #push_method<S>(mut self, item: S) -> Self
+                            where
+                                S: Into<#item>,
+                                for<'boulder_sequence> #fieldtype: ::core::iter::Extend<#item>,
+                            {
+                                ::core::iter::Extend::extend(&mut self.#fieldid, ::core::iter::once(item.into()));
+                                #mark
+                                self
+                            }
+
+                            #cfg
+                            pub fn #extend_method<I>(mut self, items: I) -> Self
+                            where
+                                I: ::std::iter::IntoIterator,
+                                I::Item: Into<#item>,
+                                for<'boulder_sequence> #fieldtype: ::core::iter::Extend<#item>,
+                            {
+                                ::core::iter::Extend::extend(&mut self.#fieldid, items.into_iter().map(Into::into));
+                                #mark
+                                self
+                            }
+
+                            #cfg
+                            pub fn #len_method(mut self, len: usize) -> Self {
+                                self.#fieldid = #regenerated;
+                                #mark
+                                self
+                            }
+                        });
+                    }
+                }
+
+                field_patterns.push(quote::quote! { #cfg #fieldid });
+                clone_body.extend(quote::quote! {
+                    #cfg #fieldid: ::core::clone::Clone::clone(&self.#fieldid),
                 });
+                setters.insert(fieldid.clone(), setter);
+            }
+
+            for preset in &presets {
+                let name = &preset.name;
+                let chain = match preset.apply(&named, quote::quote! { self }, |field, value| {
+                    Ok(setters[field.ident.as_ref().unwrap()].call(value))
+                }) {
+                    Ok(chain) => chain,
+                    Err(e) => return e.to_compile_error(),
+                };
                 methods.extend(quote::quote! {
-                    pub fn #fieldid<S>(mut self, value: S) -> Self
-                    where
-                        S: Into<#fieldtype>
-                    {
-                        self.#fieldid = value.into();
-                        self
+                    pub fn #name(self) -> Self {
+                        #chain
                     }
                 });
-                make_body.extend(quote::quote! {
-                    #fieldid: self.#fieldid,
+            }
+
+            match transients.methods(&named, true) {
+                Ok(transient_methods) => methods.extend(transient_methods),
+                Err(e) => return e.to_compile_error(),
+            }
+
+            if let Some(patch) = &patch {
+                let patch_ident = Patch::ident(&ident);
+                patch_definition = patch.definition(&ident, &vis, &full_generics, &named);
+                methods.extend(quote::quote! {
+                    /// Set each field which `patch` has a value for.
+                    pub fn apply(mut self, patch: #patch_ident #ty_generics) -> Self {
+                        let #patch_ident { #(#field_patterns),* } = patch;
+                        #apply_body
+                        self
+                    }
                 });
             }
         }
@@ -191,211 +556,361 @@ pub fn derive_buildable(input: syn::DeriveInput) -> pm2::TokenStream {
         }
     };
 
-    // if Self<T1,T2,T3> then bare generics = , T1, T2, T3
-    // bare_wc
-    let res = quote::quote! {
-        const _: () = {
-            #vis struct Builder <BoulderTypeMarkerParam #bare_generics> #wc {
-                _boulder_type_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
-                #body
-            }
-
+    // The type which the hooks receive.
+    let target = match &remote {
+        Some(remote) => {
+            let path = &remote.path;
+            quote::quote! { #path }
+        }
+        None => quote::quote! { #ident #ty_generics },
+    };
+    let run_hooks = after_build(
+        &after_build_hooks,
+        target.clone(),
+        quote::quote! { value },
+        quote::quote! { self._boulder_after_build.iter() },
+    );
+
+    let async_impls = if !cfg!(feature = "async") {
+        pm2::TokenStream::new()
+    } else if let Some(remote) = &remote {
+        let path = &remote.path;
+        let construct = remote.construct(&fieldids);
+        quote::quote! {
             #[automatically_derived]
-            impl <BoulderTypeMarkerParam #bare_generics> Builder <BoulderTypeMarkerParam #bare_ty_generics> #wc {
-                pub fn new() -> Self
-                {
-                    Self {
-                        _boulder_type_marker: Default::default(),
-                        #defaults
-                    }
-                }
-
-                fn change_type<BoulderFunctionTypeParam>(self) -> Builder<BoulderFunctionTypeParam #bare_ty_generics> {
-                    Builder {
-                        _boulder_type_marker: Default::default(),
-                        #make_body
+            impl #generics ::boulder::AsyncBuilder for Builder<#path #bare_ty_generics> #wc
+            {
+                type Result=#path;
+                fn build(self) -> impl ::core::future::Future<Output = #path> {
+                    async move {
+                        let #ident { #(#field_patterns),* } = #ident {
+                            #async_make_body
+                        };
+                        let mut value = #construct;
+                        #run_hooks
+                        value
                     }
                 }
-
-                #methods
-            }
-
-            #[automatically_derived]
-            impl #generics ::boulder::guts::builder::BoulderBase for #ident #ty_generics #wc {
-                type Base = #ident #ty_generics;
-            }
-
-            #[automatically_derived]
-            impl #generics ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for #ident #ty_generics #wc {
-                type Builder = Builder<#ident #ty_generics #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
             }
-
+        }
+    } else {
+        quote::quote! {
             #[automatically_derived]
-            impl #generics ::boulder::guts::builder::MiniBuilder for Builder<#ident #ty_generics #bare_ty_generics> #wc
+            impl #generics ::boulder::AsyncBuilder for Builder<#ident #ty_generics #bare_ty_generics> #wc
             {
                 type Result=#ident #ty_generics;
-                fn build(self) -> #ident #ty_generics {
-                    #ident {
-                        #make_body
+                fn build(self) -> impl ::core::future::Future<Output = #ident #ty_generics> {
+                    async move {
+                        let mut value = #ident {
+                            #async_make_body
+                        };
+                        #run_hooks
+                        value
                     }
                 }
             }
 
+            // Wrappers
             #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for Option<BoulderExtraGenericParam>
+            impl <BoulderExtraGenericParam #bare_generics> ::boulder::AsyncBuilder for Builder<BoulderExtraGenericParam #bare_ty_generics>
             where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
+                BoulderExtraGenericParam: ::boulder::BoulderWrapper,
+                Builder<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>: ::boulder::AsyncBuilder<Result=<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner>,
                 #bare_wc
             {
-                type Builder = Builder<Option<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                type Result = BoulderExtraGenericParam;
+                fn build(self) -> impl ::core::future::Future<Output = BoulderExtraGenericParam> {
+                    async move {
+                        <BoulderExtraGenericParam as ::boulder::BoulderWrapper>::wrap(
+                            <Builder<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics> as ::boulder::AsyncBuilder>::build(self.change_type()).await
+                        )
+                    }
                 }
             }
+        }
+    };
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<Option<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = Option<BoulderExtraGenericParam>;
-                fn build(self) -> Option<BoulderExtraGenericParam> {
-                    Some( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+    let impls = match remote {
+        Some(remote) => {
+            let path = &remote.path;
+            let construct = remote.construct(&fieldids);
+            let sync_impls = if asynchronous {
+                pm2::TokenStream::new()
+            } else {
+                quote::quote! {
+                    #[automatically_derived]
+                    impl #generics ::boulder::guts::builder::MiniBuilder for Builder<#path #bare_ty_generics> #wc
+                    {
+                        type Result=#path;
+                        fn build(self) -> #path {
+                            let #ident { #(#field_patterns),* } = #ident {
+                                #make_body
+                            };
+                            let mut value = #construct;
+                            #run_hooks
+                            value
+                        }
+                    }
+                }
+            };
+            quote::quote! {
+                #sync_impls
+
+                #[automatically_derived]
+                impl #generics #ident #ty_generics #wc {
+                    /// Create a builder for the remote type.
+                    #vis fn builder() -> Builder<#path #bare_ty_generics> {
+                        Builder::new()
+                    }
                 }
             }
+        }
+        None => {
+            let sync_impls = if asynchronous {
+                pm2::TokenStream::new()
+            } else {
+                quote::quote! {
+                    #[automatically_derived]
+                    impl #generics ::boulder::guts::builder::MiniBuilder for Builder<#ident #ty_generics #bare_ty_generics> #wc
+                    {
+                        type Result=#ident #ty_generics;
+                        fn build(self) -> #ident #ty_generics {
+                            let mut value = #ident {
+                                #make_body
+                            };
+                            #run_hooks
+                            value
+                        }
+                    }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for ::std::sync::Arc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                    #[automatically_derived]
+                    impl #generics Builder<#ident #ty_generics #bare_ty_generics> #wc {
+                        /// Build a value and store it in `repo`, after storing
+                        /// the values of its `buildable` fields, returning
+                        /// whatever the repository returns.
+                        pub fn create<BoulderRepositoryParam>(self, repo: &mut BoulderRepositoryParam) -> <BoulderRepositoryParam as ::boulder::Repository<#ident #ty_generics>>::Output
+                        where
+                            BoulderRepositoryParam: ::boulder::Repository<#ident #ty_generics>,
+                            #(#persist_types: ::boulder::Persist<BoulderRepositoryParam>,)*
+                        {
+                            let value = <Self as ::boulder::guts::builder::MiniBuilder>::build(self);
+                            #(::boulder::Persist::persist(&value.#persist_fields, repo);)*
+                            ::boulder::Repository::insert(repo, &value)
+                        }
+
+                        /// Build `n` values, taking each field which wasn't set
+                        /// on this builder from successive values of the type's
+                        /// default generator, so that they differ.
+                        pub fn build_many(&self, n: usize) -> ::std::vec::Vec<#ident #ty_generics>
+                        where
+                            // The bounds are higher-ranked so that they are
+                            // only checked when this method is used.
+                            for<'boulder_many> Self: ::core::clone::Clone,
+                            for<'boulder_many> #ident #ty_generics: ::boulder::Generatable,
+                        {
+                            let mut generator = <#ident #ty_generics as ::boulder::Generatable>::generator();
+                            (0..n)
+                                .map(|_| {
+                                    let mut builder = ::core::clone::Clone::clone(self);
+                                    #[allow(unused_variables)]
+                                    let #ident { #(#vary_patterns,)* .. } = ::boulder::Generator::generate(&mut generator);
+                                    #vary_body
+                                    <Self as ::boulder::guts::builder::MiniBuilder>::build(builder)
+                                })
+                                .collect()
+                        }
+                    }
+
+                    // Wrappers
+                    #[automatically_derived]
+                    impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<BoulderExtraGenericParam #bare_ty_generics>
+                    where
+                        BoulderExtraGenericParam: ::boulder::BoulderWrapper,
+                        Builder<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner>,
+                        #bare_wc
+                    {
+                        type Result = BoulderExtraGenericParam;
+                        fn build(self) -> BoulderExtraGenericParam {
+                            <BoulderExtraGenericParam as ::boulder::BoulderWrapper>::wrap(
+                                <Builder<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type())
+                            )
+                        }
+                    }
+                }
+            };
+            quote::quote! {
+                #sync_impls
+
+                #[automatically_derived]
+                impl <BoulderTypeMarkerParam #bare_generics> ::core::convert::From<#ident #ty_generics> for Builder<BoulderTypeMarkerParam #bare_ty_generics> #wc {
+                    fn from(value: #ident #ty_generics) -> Self {
+                        let #ident { #(#field_patterns),* } = value;
+                        Self {
+                            _boulder_type_marker: Default::default(),
+                            _boulder_transient: BoulderTransients::new(),
+                            _boulder_after_build: ::std::vec::Vec::new(),
+                            _boulder_set: {
+                                let mut set = ::std::collections::BTreeSet::new();
+                                #from_set
+                                set
+                            },
+                            #from_body
+                        }
+                    }
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::sync::Arc<BoulderExtraGenericParam>;
-                fn build(self) -> ::std::sync::Arc<BoulderExtraGenericParam> {
-                    ::std::sync::Arc::new( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+                #[automatically_derived]
+                impl #generics #ident #ty_generics #wc {
+                    /// Create a builder which starts from a copy of this
+                    /// value, rather than from the defaults.
+                    #vis fn to_builder(&self) -> Builder<#ident #ty_generics #bare_ty_generics>
+                    where
+                        // The bounds are higher-ranked so that they are
+                        // only checked when this method is used.
+                        #(for<'boulder_clone> #fieldtypes: ::core::clone::Clone,)*
+                    {
+                        Builder::from(#ident {
+                            #clone_body
+                        })
+                    }
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for ::std::rc::Rc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                #[automatically_derived]
+                impl <BoulderRepositoryParam #bare_generics> ::boulder::Persist<BoulderRepositoryParam> for #ident #ty_generics
+                where
+                    BoulderRepositoryParam: ::boulder::Repository<#ident #ty_generics>,
+                    #(#persist_types: ::boulder::Persist<BoulderRepositoryParam>,)*
+                    #bare_wc
+                {
+                    fn persist(&self, repo: &mut BoulderRepositoryParam) {
+                        #(::boulder::Persist::persist(&self.#persist_fields, repo);)*
+                        ::boulder::Repository::insert(repo, self);
+                    }
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::rc::Rc<BoulderExtraGenericParam>;
-                fn build(self) -> ::std::rc::Rc<BoulderExtraGenericParam> {
-                    ::std::rc::Rc::new( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+                #[automatically_derived]
+                impl #generics ::boulder::BuildableBase for #ident #ty_generics #wc {
+                    type Builder<BoulderExtraGenericParam> = Builder<BoulderExtraGenericParam #bare_ty_generics>;
+                    fn builder_for<BoulderExtraGenericParam>() -> Self::Builder<BoulderExtraGenericParam> {
+                        Builder::new()
+                    }
                 }
             }
+        }
+    };
+
+    // if Self<T1,T2,T3> then bare generics = , T1, T2, T3
+    // bare_wc
+    let transient_definition = transients.definition();
+    let transient_merge = transients.merge();
+
+    let res = quote::quote! {
+        const _: () = {
+            #transient_definition
+
+            #vis struct Builder <BoulderTypeMarkerParam #bare_generics> #wc {
+                _boulder_type_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
+                _boulder_transient: BoulderTransients,
+                _boulder_after_build: ::std::vec::Vec<::std::sync::Arc<dyn Fn(&mut #target) + Send + Sync>>,
+                _boulder_set: ::std::collections::BTreeSet<&'static str>,
+                #body
+            }
 
             #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for ::std::sync::Mutex<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+            impl <BoulderTypeMarkerParam #bare_generics> Builder <BoulderTypeMarkerParam #bare_ty_generics> #wc {
+                pub fn new() -> Self
+                {
+                    Self {
+                        _boulder_type_marker: Default::default(),
+                        _boulder_transient: BoulderTransients::new(),
+                        _boulder_after_build: ::std::vec::Vec::new(),
+                        _boulder_set: ::std::collections::BTreeSet::new(),
+                        #defaults
+                    }
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::sync::Mutex<BoulderExtraGenericParam>;
-                fn build(self) -> ::std::sync::Mutex<BoulderExtraGenericParam> {
-                    ::std::sync::Mutex::new( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+                fn change_type<BoulderFunctionTypeParam>(self) -> Builder<BoulderFunctionTypeParam #bare_ty_generics> {
+                    Builder {
+                        _boulder_type_marker: Default::default(),
+                        _boulder_transient: self._boulder_transient,
+                        _boulder_after_build: self._boulder_after_build,
+                        _boulder_set: self._boulder_set,
+                        #change_type_body
+                    }
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for ::std::cell::Cell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                /// Run `hook` on each value this builder builds, after any
+                /// hooks added earlier.
+                pub fn after_build<BoulderFunctionParam>(mut self, hook: BoulderFunctionParam) -> Self
+                where
+                    BoulderFunctionParam: Fn(&mut #target) + Send + Sync + 'static
+                {
+                    self._boulder_after_build.push(::std::sync::Arc::new(hook));
+                    self
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::cell::Cell<BoulderExtraGenericParam>;
-                fn build(self) -> ::std::cell::Cell<BoulderExtraGenericParam> {
-                    ::std::cell::Cell::new( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+                /// Take every field and transient value which was set on
+                /// `other`, and add its hooks after this builder's own.
+                pub fn merge(mut self, other: Self) -> Self {
+                    #merge_body
+                    #transient_merge
+                    self._boulder_after_build.extend(other._boulder_after_build);
+                    self
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuildable<#ident #ty_generics> for ::std::cell::RefCell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::builder::MiniBuildable<#ident #ty_generics>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                /// Build a value, leaving this builder as it is, so that
+                /// it can be used as a template.
+                pub fn build_ref(&self) -> <Self as ::boulder::Builder>::Result
+                where
+                    Self: ::core::clone::Clone + ::boulder::Builder,
+                {
+                    <Self as ::boulder::Builder>::build(::core::clone::Clone::clone(self))
                 }
+
+                #methods
             }
 
             #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::builder::MiniBuilder for Builder<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>
+            impl <BoulderTypeMarkerParam #bare_generics> ::core::clone::Clone for Builder <BoulderTypeMarkerParam #bare_ty_generics>
             where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::builder::MiniBuilder<Result=BoulderExtraGenericParam>,
+                // The bounds are higher-ranked so that a builder whose
+                // fields can't be cloned is still accepted.
+                #(for<'boulder_clone> #fieldtypes: ::core::clone::Clone,)*
+                for<'boulder_clone> BoulderTransients: ::core::clone::Clone,
                 #bare_wc
             {
-                type Result = ::std::cell::RefCell<BoulderExtraGenericParam>;
-                fn build(self) -> ::std::cell::RefCell<BoulderExtraGenericParam> {
-                    ::std::cell::RefCell::new( <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::builder::MiniBuilder>::build(self.change_type()) )
+                fn clone(&self) -> Self {
+                    Self {
+                        _boulder_type_marker: Default::default(),
+                        _boulder_transient: ::core::clone::Clone::clone(&self._boulder_transient),
+                        _boulder_after_build: ::core::clone::Clone::clone(&self._boulder_after_build),
+                        _boulder_set: ::core::clone::Clone::clone(&self._boulder_set),
+                        #builder_clone_body
+                    }
                 }
             }
 
+            #impls
+
+            #async_impls
         };
+
+        #patch_definition
     };
 
     res
 }
+
+/// Store the value of a field from a patch, if it has one, where
+/// `store` records a value in the builder as the field's setter does.
+fn patch_field<F>(cfg: &pm2::TokenStream, fieldid: &syn::Ident, store: F) -> pm2::TokenStream
+where
+    F: Fn(pm2::TokenStream) -> pm2::TokenStream,
+{
+    let store_value = store(quote::quote! { value });
+    quote::quote! {
+        #cfg if let ::core::option::Option::Some(value) = #fieldid {
+            #store_value
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use proc_macro2 as pm2;
+use syn::ext::IdentExt;
 
 use crate::attributes::*;
 
@@ -8,25 +9,52 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
         data,
         generics: full_generics,
         vis,
+        attrs,
         ..
     } = input;
 
+    let TypeMeta {
+        remote,
+        auto,
+        presets,
+        setter: setter_defaults,
+        transients,
+        after_build: after_build_hooks,
+        ..
+    } = match TypeMeta::from_attributes(&attrs) {
+        Ok(meta) => meta,
+        Err(e) => return e.to_compile_error(),
+    };
+
     let (generics, ty_generics, wc) = full_generics.split_for_impl();
 
     let mut body = pm2::TokenStream::new();
     let mut methods = pm2::TokenStream::new();
     let mut make_body = pm2::TokenStream::new();
+    let mut async_make_body = pm2::TokenStream::new();
+    // Whether any field has a default which must be awaited, in which
+    // case the generator only implements `AsyncGenerator`.
+    let mut asynchronous = false;
     let mut defaults = pm2::TokenStream::new();
+    let mut fieldids = Vec::new();
+    let mut field_patterns = Vec::new();
+    let mut persist_fields = Vec::new();
+    let mut persist_types = Vec::new();
 
     if let syn::Data::Struct(s) = data {
         if let syn::Fields::Named(syn::FieldsNamed { named, .. }) = s.fields {
             for field in named.iter() {
                 let fieldid = field.ident.as_ref().unwrap();
                 let fieldtype = &field.ty;
+                fieldids.push(fieldid.clone());
+                let cfg = forwarded_attributes(field, &["cfg"]);
+                let attrs = forwarded_attributes(field, &["doc", "cfg"]);
+                field_patterns.push(quote::quote! { #cfg #fieldid });
                 let mut builder = BuildType::Default;
                 let mut generator = GeneratorType::Default;
                 let mut sequence = None;
                 let mut build_sequence = None;
+                let mut recursive = None;
 
                 for attr in field.attrs.iter() {
                     if attr.path.is_ident("boulder") {
@@ -46,6 +74,34 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
                         if build_sequence.is_none() {
                             build_sequence = parsed.builder.sequence;
                         }
+                        if recursive.is_none() {
+                            recursive = parsed.generator.recursive;
+                        }
+                    }
+                }
+
+                // Fields which may be compiled out can't appear in the
+                // bounds, so they are not stored.
+                if (matches!(builder, BuildType::Buildable(_))
+                    || matches!(generator, GeneratorType::Generatable(_)))
+                    && cfg.is_empty()
+                {
+                    persist_fields.push(fieldid.clone());
+                    persist_types.push(fieldtype.clone());
+                }
+
+                if auto
+                    && matches!(builder, BuildType::Default)
+                    && matches!(generator, GeneratorType::Default)
+                    && sequence.is_none()
+                    && build_sequence.is_none()
+                    && recursive.is_none()
+                {
+                    if let Some(expr) = auto_generator(fieldid, fieldtype) {
+                        generator = GeneratorType::Generator {
+                            expr: Box::new(expr),
+                            ty: None,
+                        };
                     }
                 }
 
@@ -57,11 +113,87 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
                     })
                 };
 
+                if let (
+                    GeneratorType::Default,
+                    BuildType::DefaultFrom(expr) | BuildType::DefaultAsync(expr),
+                ) = (&generator, &builder)
+                {
+                    let is_async = matches!(builder, BuildType::DefaultAsync(_));
+                    if sequence.is_some() || recursive.is_some() {
+                        return syn::Error::new_spanned(
+                            fieldid,
+                            format!(
+                                "{} cannot be combined with sequence or recursive",
+                                if is_async {
+                                    "default_async"
+                                } else {
+                                    "default_from"
+                                }
+                            ),
+                        )
+                        .to_compile_error();
+                    }
+                    // The default depends on the transient values, or
+                    // must be awaited, so there is no generator until
+                    // one is set.
+                    let (default, async_default) = if is_async {
+                        asynchronous = true;
+                        (
+                            None,
+                            quote::quote! {
+                                {
+                                    let value: #fieldtype = (#expr).await;
+                                    value
+                                }
+                            },
+                        )
+                    } else {
+                        let default = default_from(
+                            expr,
+                            fieldtype,
+                            quote::quote! { &gen._boulder_transient },
+                        );
+                        (Some(default.clone()), default)
+                    };
+                    body.extend(quote::quote! {
+                        #cfg #fieldid: ::core::option::Option<Box<dyn ::boulder::Generator<Output=#fieldtype>>>,
+                    });
+                    methods.extend(quote::quote! {
+                        #attrs
+                        pub fn #fieldid<V>(mut self, generator: V) -> Self
+                        where
+                            V: 'static + ::boulder::Generator<Output=#fieldtype>
+                        {
+                            self.#fieldid = ::core::option::Option::Some(Box::new(generator));
+                            self
+                        }
+                    });
+                    if let Some(default) = default {
+                        make_body.extend(quote::quote! {
+                            #cfg #fieldid: match &mut gen.#fieldid {
+                                ::core::option::Option::Some(generator) => generator.generate(),
+                                ::core::option::Option::None => #default,
+                            },
+                        });
+                    }
+                    async_make_body.extend(quote::quote! {
+                        #cfg #fieldid: match &mut gen.#fieldid {
+                            ::core::option::Option::Some(generator) => generator.generate(),
+                            ::core::option::Option::None => #async_default,
+                        },
+                    });
+                    defaults.extend(quote::quote! {
+                        #cfg #fieldid: ::core::option::Option::None,
+                    });
+                    continue;
+                }
+
                 body.extend(quote::quote! {
-                    #fieldid: Box<dyn ::boulder::Generator<Output=#fieldtype>>,
+                    #cfg #fieldid: Box<dyn ::boulder::Generator<Output=#fieldtype>>,
                 });
 
                 methods.extend(quote::quote! {
+                    #attrs
                     pub fn #fieldid<V>(mut self, generator: V) -> Self
                     where
                         V: 'static + ::boulder::Generator<Output=#fieldtype>
@@ -71,16 +203,28 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
                     }
                 });
 
-                make_body.extend(quote::quote! {
-                    #fieldid: gen.#fieldid.generate(),
-                });
+                let value = quote::quote! {
+                    #cfg #fieldid: gen.#fieldid.generate(),
+                };
+                make_body.extend(value.clone());
+                async_make_body.extend(value);
 
-                let element_type = if sequence.is_some() {
+                if recursive.is_some() {
+                    if let GeneratorType::Default = generator {
+                        generator = GeneratorType::Generatable(Default::default());
+                    }
+                }
+
+                let element_type = if sequence.is_some() || recursive.is_some() {
                     quote::quote! { <#fieldtype as std::iter::IntoIterator>::Item }
                 } else {
                     quote::quote! { #fieldtype }
                 };
 
+                let nested = matches!(generator, GeneratorType::Generatable(_))
+                    && sequence.is_none()
+                    && recursive.is_none();
+
                 let value = match generator {
                     GeneratorType::Generator { expr, .. } => {
                         quote::quote! {
@@ -124,6 +268,9 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
                                     (#value).into()
                                 });
                             }
+                            BuildType::DefaultFrom(_) | BuildType::DefaultAsync(_) => {
+                                unreachable!("deferred defaults are handled before the generator")
+                            }
                             BuildType::Default => {
                                 static_value.extend(quote::quote! {
                                     Default::default()
@@ -136,24 +283,91 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
                     }
                 };
 
-                if let Some(sequence) = sequence {
+                if let Some(Recursion {
+                    max_depth,
+                    branching,
+                }) = recursive
+                {
+                    let branching = match (branching, sequence) {
+                        (Some(branching), _) => quote::quote! { #branching },
+                        (None, Some(sequence)) => sequence,
+                        (None, None) => {
+                            return syn::Error::new_spanned(
+                                fieldid,
+                                "recursive requires either branching or a sequence",
+                            )
+                            .to_compile_error()
+                        }
+                    };
+                    defaults.extend(quote::quote! {
+                        #cfg #fieldid: Box::new(::boulder::Recursive::new(
+                            move || { #value },
+                            #branching,
+                            (#max_depth) as usize,
+                        )),
+                    })
+                } else if let Some(sequence) = sequence {
+                    let collected = collect_sequence(
+                        fieldtype,
+                        quote::quote! {
+                            ::boulder::GeneratorMutIterator::new(
+                                &mut value
+                            ).take(::boulder::Generator::generate(&mut seq).into())
+                        },
+                    );
                     defaults.extend(quote::quote! {
-                        #fieldid: {
+                        #cfg #fieldid: {
                             let mut seq = #sequence;
                             let mut value = { #value };
                             Box::new(move || {
-                                ::boulder::GeneratorMutIterator::new(
-                                    &mut value
-                                ).take(::boulder::Generator::generate(&mut seq).into()).collect()
+                                ::boulder::Generator::begin_sequence(&mut value);
+                                #collected
                             })
                         },
                     })
                 } else {
+                    if nested {
+                        let with_method = quote::format_ident!("{}_with", fieldid.unraw());
+                        methods.extend(quote::quote! {
+                            #cfg
+                            pub fn #with_method<F>(mut self, f: F) -> Self
+                            where
+                                F: FnOnce(<#fieldtype as ::boulder::Generatable>::Generator) -> <#fieldtype as ::boulder::Generatable>::Generator
+                            {
+                                self.#fieldid = Box::new(f(#value));
+                                self
+                            }
+                        });
+                    }
                     defaults.extend(quote::quote! {
-                        #fieldid: Box::new(#value),
+                        #cfg #fieldid: Box::new(#value),
                     })
                 }
             }
+
+            for preset in &presets {
+                let name = &preset.name;
+                let chain = match preset.apply(&named, quote::quote! { self }, |field, value| {
+                    let (name, ty) = (&field.ident, &field.ty);
+                    let value = SetterOptions::setter(&setter_defaults, field)?.preset_value(value);
+                    Ok(quote::quote! {
+                        .#name(|| -> #ty { #value })
+                    })
+                }) {
+                    Ok(chain) => chain,
+                    Err(e) => return e.to_compile_error(),
+                };
+                methods.extend(quote::quote! {
+                    pub fn #name(self) -> Self {
+                        #chain
+                    }
+                });
+            }
+
+            match transients.methods(&named, false) {
+                Ok(transient_methods) => methods.extend(transient_methods),
+                Err(e) => return e.to_compile_error(),
+            }
         }
     }
 
@@ -192,6 +406,30 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
         res
     };
 
+    let nested_args = {
+        let mut res = pm2::TokenStream::new();
+        for p in &full_generics.params {
+            match p {
+                syn::GenericParam::Type(syn::TypeParam { ident, .. }) => {
+                    res.extend(quote::quote! {
+                        #ident,
+                    });
+                }
+                syn::GenericParam::Lifetime(syn::LifetimeDef { lifetime, .. }) => {
+                    res.extend(quote::quote! {
+                        #lifetime,
+                    });
+                }
+                syn::GenericParam::Const(syn::ConstParam { ident, .. }) => {
+                    res.extend(quote::quote! {
+                        #ident,
+                    });
+                }
+            }
+        }
+        res
+    };
+
     let bare_wc = {
         let wc = &full_generics.where_clause.as_ref().map(|w| &w.predicates);
 
@@ -200,226 +438,266 @@ pub fn derive_generatable(input: syn::DeriveInput) -> pm2::TokenStream {
         }
     };
 
-    let res = quote::quote! {
-        const _: () = {
-            #vis struct Generator<BoulderTypeMarkerParam #bare_generics> #wc {
-                _boulder_type_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
-                #body
-            }
-
-            #vis trait NestedGenerate #generics #wc {
-                type Output;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output;
+    // The type which the hooks receive.
+    let target = match &remote {
+        Some(remote) => {
+            let path = &remote.path;
+            quote::quote! { #path }
+        }
+        None => quote::quote! { #ident #ty_generics },
+    };
+    let run_hooks = after_build(
+        &after_build_hooks,
+        target.clone(),
+        quote::quote! { value },
+        quote::quote! { gen._boulder_after_build.iter_mut() },
+    );
+
+    let async_impls = if !cfg!(feature = "async") {
+        pm2::TokenStream::new()
+    } else {
+        let base = match &remote {
+            Some(remote) => {
+                let path = &remote.path;
+                let construct = remote.construct(&fieldids);
+                quote::quote! {
+                    #[automatically_derived]
+                    impl #generics NestedGenerateAsync #ty_generics for Generator<#path #bare_ty_generics> #wc {
+                        type Output = #path;
+                        fn nested_generate_async<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> impl ::core::future::Future<Output = Self::Output> {
+                            async move {
+                                let #ident { #(#field_patterns),* } = #ident {
+                                    #async_make_body
+                                };
+                                let mut value = #construct;
+                                #run_hooks
+                                value
+                            }
+                        }
+                    }
+                }
             }
+            None => quote::quote! {
+                #[automatically_derived]
+                impl #generics NestedGenerateAsync #ty_generics for Generator<#ident #ty_generics #bare_ty_generics> #wc {
+                    type Output = #ident #ty_generics;
+                    fn nested_generate_async<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> impl ::core::future::Future<Output = Self::Output> {
+                        async move {
+                            let mut value = #ident {
+                                #async_make_body
+                            };
+                            #run_hooks
+                            value
+                        }
+                    }
+                }
 
-            #[automatically_derived]
-            impl<BoulderTypeMarkerParam #bare_generics> Generator <BoulderTypeMarkerParam #bare_ty_generics> #wc {
-                pub fn new() -> Self
+                // Wrappers
+                #[automatically_derived]
+                impl <BoulderExtraGenericParam #bare_generics> NestedGenerateAsync #ty_generics for Generator<BoulderExtraGenericParam #bare_ty_generics>
+                where
+                    BoulderExtraGenericParam: ::boulder::BoulderWrapper,
+                    Generator<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>: NestedGenerateAsync<#nested_args Output=<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner>,
+                    #bare_wc
                 {
-                    Self {
-                        _boulder_type_marker: Default::default(),
-                        #defaults
+                    type Output = BoulderExtraGenericParam;
+                    fn nested_generate_async<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> impl ::core::future::Future<Output = Self::Output> {
+                        async move {
+                            <BoulderExtraGenericParam as ::boulder::BoulderWrapper>::wrap(
+                                Generator::<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>::nested_generate_async(gen).await
+                            )
+                        }
                     }
                 }
-
-                #methods
+            },
+        };
+        quote::quote! {
+            #vis trait NestedGenerateAsync #generics #wc {
+                type Output;
+                fn nested_generate_async<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> impl ::core::future::Future<Output = Self::Output>;
             }
 
             #[automatically_derived]
-            impl<BoulderTypeMarkerParam #bare_generics> ::boulder::guts::generator::MiniGenerator for Generator<BoulderTypeMarkerParam #bare_ty_generics>
-
+            impl<BoulderTypeMarkerParam #bare_generics> ::boulder::AsyncGenerator for Generator<BoulderTypeMarkerParam #bare_ty_generics>
             where
-                Self: NestedGenerate #ty_generics,
+                Self: NestedGenerateAsync #ty_generics,
                 BoulderTypeMarkerParam: 'static,
                 #bare_wc
             {
-                type Output = <Self as NestedGenerate #ty_generics>::Output;
-                fn generate(&mut self) -> Self::Output {
-                    <Self as NestedGenerate #ty_generics>::nested_generate(self)
+                type Output = <Self as NestedGenerateAsync #ty_generics>::Output;
+                fn generate(&mut self) -> impl ::core::future::Future<Output = Self::Output> {
+                    <Self as NestedGenerateAsync #ty_generics>::nested_generate_async(self)
                 }
             }
 
-            #[automatically_derived]
-            impl #generics ::boulder::guts::generator::BoulderBase for #ident #ty_generics #wc {
-                type Base = #ident #ty_generics;
-            }
-
-            // Base case
+            #base
+        }
+    };
 
-            #[automatically_derived]
-            impl #generics ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for #ident #ty_generics #wc {
-                type Generator = Generator<#ident #ty_generics #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
+    let impls = match remote {
+        Some(remote) => {
+            let path = &remote.path;
+            let construct = remote.construct(&fieldids);
+            let sync_impls = if asynchronous {
+                pm2::TokenStream::new()
+            } else {
+                quote::quote! {
+                    #[automatically_derived]
+                    impl #generics NestedGenerate #ty_generics for Generator<#path #bare_ty_generics> #wc {
+                        type Output = #path;
+                        fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
+                            let #ident { #(#field_patterns),* } = #ident {
+                                #make_body
+                            };
+                            let mut value = #construct;
+                            #run_hooks
+                            value
+                        }
+                    }
                 }
-            }
-
-            #[automatically_derived]
-            impl #generics NestedGenerate #ty_generics for Generator<#ident #ty_generics #bare_ty_generics> #wc {
-                type Output = #ident #ty_generics;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    #ident {
-                        #make_body
+            };
+            quote::quote! {
+                #sync_impls
+
+                #[automatically_derived]
+                impl #generics #ident #ty_generics #wc {
+                    /// Create a generator for the remote type.
+                    #vis fn generator() -> Generator<#path #bare_ty_generics> {
+                        Generator::new()
                     }
                 }
             }
+        }
+        None => {
+            let sync_impls = if asynchronous {
+                pm2::TokenStream::new()
+            } else {
+                quote::quote! {
+                    // Base case
+
+                    #[automatically_derived]
+                    impl #generics NestedGenerate #ty_generics for Generator<#ident #ty_generics #bare_ty_generics> #wc {
+                        type Output = #ident #ty_generics;
+                        fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
+                            let mut value = #ident {
+                                #make_body
+                            };
+                            #run_hooks
+                            value
+                        }
+                    }
 
-            // Option
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for Option<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<Option<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=Option<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<Option<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
+                    #[automatically_derived]
+                    impl #generics Generator<#ident #ty_generics #bare_ty_generics> #wc {
+                        /// Generate a value and store it in `repo`, after
+                        /// storing the values of its `buildable` and
+                        /// `generatable` fields, returning whatever the
+                        /// repository returns.
+                        pub fn create<BoulderRepositoryParam>(&mut self, repo: &mut BoulderRepositoryParam) -> <BoulderRepositoryParam as ::boulder::Repository<#ident #ty_generics>>::Output
+                        where
+                            BoulderRepositoryParam: ::boulder::Repository<#ident #ty_generics>,
+                            #(#persist_types: ::boulder::Persist<BoulderRepositoryParam>,)*
+                        {
+                            let value = <Self as NestedGenerate #ty_generics>::nested_generate(self);
+                            #(::boulder::Persist::persist(&value.#persist_fields, repo);)*
+                            ::boulder::Repository::insert(repo, &value)
+                        }
+                    }
                 }
-            }
-
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<Option<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
-                #bare_wc
-            {
-                type Output = Option<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    Some( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
+            };
+            quote::quote! {
+                #sync_impls
+
+                #[automatically_derived]
+                impl #generics ::boulder::GeneratableBase for #ident #ty_generics #wc {
+                    type Generator<BoulderExtraGenericParam> = Generator<BoulderExtraGenericParam #bare_ty_generics>;
+                    fn generator_for<BoulderExtraGenericParam>() -> Self::Generator<BoulderExtraGenericParam> {
+                        Generator::new()
+                    }
                 }
-            }
 
-            // Rc
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for ::std::rc::Rc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=::std::rc::Rc<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
+                // Wrappers
+                #[automatically_derived]
+                impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<BoulderExtraGenericParam #bare_ty_generics>
+                where
+                    BoulderExtraGenericParam: ::boulder::BoulderWrapper,
+                    Generator<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>: NestedGenerate<#nested_args Output=<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner>,
+                    #bare_wc
+                {
+                    type Output = BoulderExtraGenericParam;
+                    fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
+                        <BoulderExtraGenericParam as ::boulder::BoulderWrapper>::wrap(
+                            Generator::<<BoulderExtraGenericParam as ::boulder::BoulderWrapper>::Inner #bare_ty_generics>::nested_generate(gen)
+                        )
+                    }
                 }
             }
+        }
+    };
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
-                #bare_wc
-            {
-                type Output = ::std::rc::Rc<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    ::std::rc::Rc::new( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
-                }
-            }
+    let transient_definition = transients.definition();
 
-            // Arc
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for ::std::sync::Arc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=::std::sync::Arc<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
+    let res = quote::quote! {
+        const _: () = {
+            #transient_definition
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
-                #bare_wc
-            {
-                type Output = ::std::sync::Arc<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    ::std::sync::Arc::new( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
-                }
+            #vis struct Generator<BoulderTypeMarkerParam #bare_generics> #wc {
+                _boulder_type_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
+                _boulder_transient: BoulderTransients,
+                // Unlike a builder, a generator is neither cloned nor
+                // sent between threads, so its hooks can be stateful.
+                _boulder_after_build: ::std::vec::Vec<Box<dyn FnMut(&mut #target)>>,
+                #body
             }
 
-            // Mutex
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for ::std::sync::Mutex<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=::std::sync::Mutex<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
+            #vis trait NestedGenerate #generics #wc {
+                type Output;
+                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output;
             }
 
             #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
-                #bare_wc
-            {
-                type Output = ::std::sync::Mutex<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    ::std::sync::Mutex::new( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
+            impl<BoulderTypeMarkerParam #bare_generics> Generator <BoulderTypeMarkerParam #bare_ty_generics> #wc {
+                pub fn new() -> Self
+                {
+                    Self {
+                        _boulder_type_marker: Default::default(),
+                        _boulder_transient: BoulderTransients::new(),
+                        _boulder_after_build: ::std::vec::Vec::new(),
+                        #defaults
+                    }
                 }
-            }
 
-            // Cell
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for ::std::cell::Cell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=::std::cell::Cell<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
+                /// Run `hook` on each value this generator generates,
+                /// after any hooks added earlier.
+                pub fn after_build<BoulderFunctionParam>(mut self, hook: BoulderFunctionParam) -> Self
+                where
+                    BoulderFunctionParam: FnMut(&mut #target) + 'static
+                {
+                    self._boulder_after_build.push(Box::new(hook));
+                    self
                 }
-            }
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
-                #bare_wc
-            {
-                type Output = ::std::cell::Cell<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    ::std::cell::Cell::new( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
-                }
+                #methods
             }
 
-            // RefCell
             #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics> for ::std::cell::RefCell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::generator::MiniGeneratable<#ident #ty_generics>,
-                Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>: ::boulder::guts::generator::MiniGenerator<Output=::std::cell::RefCell<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
+            impl<BoulderTypeMarkerParam #bare_generics> ::boulder::guts::generator::MiniGenerator for Generator<BoulderTypeMarkerParam #bare_ty_generics>
 
-            #[automatically_derived]
-            impl <BoulderExtraGenericParam #bare_generics> NestedGenerate #ty_generics for Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>
             where
-                Generator<BoulderExtraGenericParam #bare_ty_generics>: NestedGenerate #ty_generics,
+                Self: NestedGenerate #ty_generics,
+                BoulderTypeMarkerParam: 'static,
                 #bare_wc
             {
-                type Output = ::std::cell::RefCell<<Generator<BoulderExtraGenericParam #bare_ty_generics> as NestedGenerate #ty_generics>::Output>;
-                fn nested_generate<BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics>) -> Self::Output {
-                    ::std::cell::RefCell::new( Generator::<BoulderExtraGenericParam #bare_ty_generics>::nested_generate(gen) )
+                type Output = <Self as NestedGenerate #ty_generics>::Output;
+                fn generate(&mut self) -> Self::Output {
+                    <Self as NestedGenerate #ty_generics>::nested_generate(self)
                 }
             }
 
+            #impls
+
+            #async_impls
+
             // Iterators
 
             #[automatically_derived]
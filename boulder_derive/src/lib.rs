@@ -3,6 +3,7 @@ mod builder;
 mod generator;
 mod repeat;
 mod string;
+mod weighted;
 
 use proc_macro::{self, TokenStream};
 
@@ -21,13 +22,22 @@ use proc_macro::{self, TokenStream};
 ///
 /// - `#[boulder(buildable)]` The type for this field implements
 ///   `Buildable` itself, so new values should be constructed using
-///   `T::builder().build()`.
+///   `T::builder().build()`. Numbers, strings, standard collections,
+///   tuples, arrays and chrono's date and time types all implement
+///   `Buildable`.
 ///
 /// - `#[boulder(buildable(a=5, b=10))]` The type for this field implements
 ///   `Buildable`, and new instances should be customised from the
-///    default by setting `a=5` and `b=10` where `a` and `b` are
-///    member names, and `5` and `10` can be replaced by arbitrary
-///    well-formed Rust expressions.
+///   default by setting `a=5` and `b=10` where `a` and `b` are
+///   member names, and `5` and `10` can be replaced by arbitrary
+///   well-formed Rust expressions. For either form, the builder
+///   also gets a `foo_with` method for a field `foo`, which takes a
+///   closure that further customises the nested builder, starting
+///   from `a=5` and `b=10`; the nested value is built when the outer
+///   `build()` is called. The closure must be `Send + Sync`, so
+///   that the builder can be shared between threads; this means it
+///   can't capture `Rc` or `RefCell` state, so use `Arc` and a
+///   `Mutex` or atomic to share state with it instead.
 ///
 /// - `#[boulder(sequence=3)]` This field is assumed to be a
 ///   collection type (a type which can be the target of
@@ -37,7 +47,353 @@ use proc_macro::{self, TokenStream};
 ///   generator specification (`generator` or `generatable`) if one is
 ///   given; otherwise it will be from the builder specification
 ///   (`default` or `buildable`, as described above) if one is given;
-///   otherwise the items will be default initialized.
+///   otherwise the items will be default initialized. Arrays are
+///   supported too, in which case the number of items must match
+///   the array length.
+///
+///   For a field `foo` marked this way, the builder also has
+///   `push_foo(item)` and `extend_foo(items)`, which add to the
+///   current collection, and `foo_len(n)`, which replaces it with
+///   `n` newly made items; none of these exist for arrays. When the
+///   items are `buildable`, `foo_at(i, |b| ...)` rebuilds the item
+///   at index `i`, passing the closure a builder which starts from
+///   that item.
+///
+/// Named presets can be declared on the struct itself:
+///
+/// - `#[boulder(preset(admin, role="admin", active=true))]` Add a
+///   method `admin()` to the builder, which sets `role` and `active`
+///   as if their setters had been called with these values. Presets
+///   can be combined, and any setter called later wins, as does any
+///   preset applied later.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// #[boulder(preset(admin, role="admin", active=true), preset(suspended, active=false))]
+/// struct User {
+///     #[boulder(default="member")]
+///     role: String,
+///     active: bool,
+/// }
+///
+/// let u = User::builder().admin().build();
+/// assert_eq!((u.role.as_str(), u.active), ("admin", true));
+/// let u = User::builder().admin().suspended().build();
+/// assert_eq!((u.role.as_str(), u.active), ("admin", false));
+/// ```
+///
+/// To start from an existing value instead of the defaults, for
+/// example to copy a fixture with a few changes, use
+/// `Foo::to_builder(&self)`, which clones every field, or
+/// `<Foo as Buildable>::Builder::from(foo)`, which moves them. The
+/// remote mirror types described below have neither.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// struct Task {
+///     #[boulder(default="write docs")]
+///     title: String,
+///     done: bool,
+/// }
+///
+/// let t = Task::builder().title("fix bug").build();
+/// let t2 = t.to_builder().done(true).build();
+/// assert_eq!((t2.title.as_str(), t2.done), ("fix bug", true));
+/// let t3 = <Task as Buildable>::Builder::from(t).title("test fix").build();
+/// assert_eq!((t3.title.as_str(), t3.done), ("test fix", false));
+/// ```
+///
+/// Each setter takes any value which converts into the field type
+/// with `Into`. This can be changed with `#[boulder(setter(...))]`,
+/// either on a field or on the struct, in which case it applies to
+/// every field which doesn't say otherwise:
+///
+/// - `strip_option` For a field of type `Option<T>`, the setter takes
+///   a `T` and stores `Some` of it. On the struct, this only affects
+///   `Option` fields. Use `strip_option = false` to turn it off for
+///   one field.
+///
+/// - `into = false` The setter takes exactly the field type (or `T`,
+///   with `strip_option`), which avoids inference problems, for
+///   example with numeric literals.
+///
+/// - `prefix = "with_"` Name the setter for field `foo` `with_foo`.
+///
+/// Every `Option` field also gets an `unset_foo()` method, which sets
+/// it to `None`. Doc comments and `#[cfg]` attributes on a field are
+/// copied onto its setter, and `#[allow]` attributes onto the
+/// builder's copy of the field.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// #[boulder(setter(strip_option))]
+/// struct Account {
+///     #[boulder(default=Some("guest".to_string()))]
+///     name: Option<String>,
+///     #[boulder(default=1.5, setter(into = false, prefix = "with_"))]
+///     limit: f64,
+/// }
+///
+/// let a = Account::builder().name("admin").with_limit(2.0).build();
+/// assert_eq!(a.name.as_deref(), Some("admin"));
+/// assert_eq!(a.limit, 2.0);
+/// let a = Account::builder().unset_name().build();
+/// assert_eq!(a.name, None);
+/// ```
+///
+/// Some defaults depend on what kind of value is being built, rather
+/// than on any one field. Transient parameters describe this: they
+/// have setters on the builder, but aren't stored in the value.
+///
+/// - `#[boulder(transient(premium: bool = false, seats: usize))]` on
+///   the struct itself adds `premium(value)` and `seats(value)` to
+///   the builder. A transient with no `= value` starts from its
+///   type's default. Transients can't share a name with a field, and
+///   their types can't use the struct's type parameters.
+///
+/// - `#[boulder(default_from = |t| if t.premium { 100 } else { 10 })]`
+///   The default value for this field is computed from the transient
+///   values when `build()` is called, by a closure which takes them
+///   by reference and doesn't capture anything. Calling the field's
+///   setter overrides it. This can't be combined with `sequence`.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// #[boulder(transient(premium: bool))]
+/// struct Plan {
+///     #[boulder(default_from = |t| if t.premium { 100 } else { 10 })]
+///     quota: u32,
+/// }
+///
+/// assert_eq!(Plan::builder().build().quota, 10);
+/// assert_eq!(Plan::builder().premium(true).build().quota, 100);
+/// assert_eq!(Plan::builder().premium(true).quota(5u32).build().quota, 5);
+/// ```
+///
+/// To adjust a value once it has been built, for example to keep
+/// two fields consistent, use a hook:
+///
+/// - `#[boulder(after_build = path::to::fn)]` on the struct itself
+///   calls the function, which takes `&mut Foo`, on every value the
+///   builder builds. This can be repeated, and the hooks run in
+///   order.
+///
+/// - `.after_build(|foo| ...)` on a builder adds a closure taking
+///   `&mut Foo`, which runs after the struct's own hooks and any
+///   closures added before it. The closure must be `Send + Sync`, so
+///   that the builder can still be shared between threads.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// fn tidy(n: &mut Note) {
+///     n.text = n.text.trim().to_string();
+/// }
+///
+/// #[derive(Buildable)]
+/// #[boulder(after_build = tidy)]
+/// struct Note {
+///     #[boulder(default=" hello ")]
+///     text: String,
+///     length: usize,
+/// }
+///
+/// let n = Note::builder().after_build(|n| n.length = n.text.len()).build();
+/// assert_eq!((n.text.as_str(), n.length), ("hello", 5));
+/// ```
+///
+/// To store what is built, rather than just building it, use
+/// `create(&mut repo)` instead of `build()`, where `repo` implements
+/// `Repository` for the type. The values of the `buildable` fields
+/// are stored first, along with their own `buildable` fields, and
+/// then the value itself; `create` returns whatever the repository
+/// returns for it, such as an ID. `MemoryRepository` stores values of
+/// any type in memory. Deriving `Buildable` also implements `Persist`
+/// for the type, which is how its nested values are stored, including
+/// through wrappers such as `Option`, `Box` or `Mutex`. The derived
+/// `Persist` skips `buildable` fields with `#[cfg]` attributes, so
+/// their values are not stored, and the remote mirror types
+/// described below have no `create`.
+///
+/// ```rust
+/// use boulder::{Buildable, MemoryRepository};
+///
+/// #[derive(Buildable, Clone)]
+/// struct Author {
+///     #[boulder(default="Ursula")]
+///     name: String,
+/// }
+///
+/// #[derive(Buildable, Clone)]
+/// struct Book {
+///     #[boulder(buildable)]
+///     author: Author,
+///     title: String,
+/// }
+///
+/// let mut repo = MemoryRepository::new();
+/// let id = Book::builder().title("Lathe").create(&mut repo);
+/// assert_eq!(repo.get::<Book>(id).unwrap().title, "Lathe");
+/// assert_eq!(repo.get::<Author>(0).unwrap().name, "Ursula");
+/// ```
+///
+/// Fixtures can also be layered, keeping overrides as data rather
+/// than as chains of setter calls:
+///
+/// - `#[boulder(patch)]` on the struct itself also defines `FooPatch`,
+///   with the same fields and visibility as `Foo`, but with every
+///   field wrapped in `Option`. It implements `Default`, with every
+///   field `None`; use `patch(derive(Clone, Debug))` to derive other
+///   traits for it as well. The builder gains `.apply(patch)`, which
+///   sets each field that the patch has a value for, exactly as the
+///   field's setter would.
+///
+/// - `.merge(other)` on any builder takes each field and transient
+///   value that was set on `other`, whether by a setter, a preset,
+///   `apply`, or by starting from a value, so the later builder wins.
+///   Hooks added to `other` with `.after_build(...)` run after the
+///   first builder's.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// #[derive(Buildable)]
+/// #[boulder(patch)]
+/// struct Fixture {
+///     #[boulder(default="base")]
+///     name: String,
+///     #[boulder(default=1)]
+///     count: i32,
+/// }
+///
+/// let patch = FixturePatch { count: Some(5), ..Default::default() };
+/// let f = Fixture::builder().apply(patch).build();
+/// assert_eq!((f.name.as_str(), f.count), ("base", 5));
+///
+/// let base = Fixture::builder().name("shared").count(2);
+/// let f = base.merge(Fixture::builder().count(3)).build();
+/// assert_eq!((f.name.as_str(), f.count), ("shared", 3));
+/// ```
+///
+/// A configured builder can also be kept as a template. It
+/// implements `Clone` when the types of all the fields and transient
+/// values do, and then:
+///
+/// - `.build_ref()` builds a value without consuming the builder.
+///
+/// - `.build_many(n)` builds `n` values. Each field which wasn't set
+///   on the builder is taken from successive values of the type's
+///   default generator, so the type must also derive `Generatable`.
+///   Fields with `default_from` keep that default.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder, Generatable, Inc};
+///
+/// #[derive(Buildable, Generatable, Clone)]
+/// struct Seat {
+///     #[boulder(default=1, generator=Inc(1))]
+///     number: i32,
+///     #[boulder(default="economy")]
+///     class: String,
+/// }
+///
+/// let template = Seat::builder().class("business");
+/// assert_eq!(template.build_ref().number, 1);
+/// let seats = template.build_many(3);
+/// let numbers: Vec<_> = seats.iter().map(|s| s.number).collect();
+/// assert_eq!(numbers, vec![1, 2, 3]);
+/// assert!(seats.iter().all(|s| s.class == "business"));
+/// ```
+///
+/// With boulder's `"async"` feature enabled, some defaults can come
+/// from a future instead, for example an ID from a stub service:
+///
+/// - `#[boulder(default_async = allocate_id())]` The default value for
+///   this field is the output of this future, which must be the
+///   field's type. It is only awaited by `AsyncBuilder::build`, as
+///   `Foo::async_builder().build().await`, and only when the field
+///   hasn't been set. A builder with such a field implements
+///   `AsyncBuilder` instead of `Builder`, so there is no synchronous
+///   path at all: `Foo` is `AsyncBuildable` rather than `Buildable`,
+///   its builder has no `create` or `build_many`, and it can't be a
+///   `buildable` field of another type. Wrappers such as
+///   `Option<Foo>` still pass through asynchronously. This can't be
+///   combined with `sequence`, and `buildable` fields are still built
+///   synchronously, so use
+///   `default_async = Bar::async_builder().build()` for a
+///   nested value with asynchronous defaults of its own. Nothing
+///   depends on a particular async runtime; see `AsyncBuilder` for an
+///   example.
+///
+/// Types defined in other crates can't derive `Buildable`, but a
+/// local mirror struct can stand in for them, in the same way as
+/// serde's remote derive:
+///
+/// - `#[boulder(remote = "other_crate::Config")]` on the struct
+///   itself makes the builder produce an `other_crate::Config`
+///   instead of the mirror type. The mirror's fields must match the
+///   remote type's fields in both name and type. The mirror gets an
+///   inherent `builder()` method, rather than an implementation of
+///   `Buildable`, so use `#[boulder(default=ConfigDef::builder().build())]`
+///   for fields of the remote type.
+///
+/// - `#[boulder(constructor = "other_crate::Config::new")]` Build the
+///   remote type by calling this function with the mirror's fields as
+///   arguments, in declaration order, instead of with a struct
+///   literal. This is needed when the remote type's fields are not
+///   public.
+///
+/// ```rust
+/// use boulder::{Buildable, Builder};
+///
+/// mod other_crate {
+///     pub struct Config {
+///         pub name: String,
+///         pub retries: u32,
+///     }
+///
+///     pub struct Secret(String);
+///
+///     impl Secret {
+///         pub fn new(value: String) -> Self {
+///             Secret(value)
+///         }
+///         pub fn value(&self) -> &str {
+///             &self.0
+///         }
+///     }
+/// }
+///
+/// #[derive(Buildable)]
+/// #[boulder(remote = "other_crate::Config")]
+/// struct ConfigDef {
+///     #[boulder(default="server")]
+///     name: String,
+///     #[boulder(default=3u32)]
+///     retries: u32,
+/// }
+///
+/// #[derive(Buildable)]
+/// #[boulder(remote = "other_crate::Secret", constructor = "other_crate::Secret::new")]
+/// struct SecretDef {
+///     #[boulder(default="hunter2")]
+///     value: String,
+/// }
+///
+/// let config = ConfigDef::builder().retries(5u32).build();
+/// assert_eq!(config.name, "server");
+/// assert_eq!(config.retries, 5);
+/// let secret = SecretDef::builder().build();
+/// assert_eq!(secret.value(), "hunter2");
+/// ```
 ///
 /// Example:
 /// ```rust
@@ -108,13 +464,20 @@ pub fn builder(input: TokenStream) -> TokenStream {
 /// - `#[boulder(generatable)]` The type for this field implements
 ///   `Generatable`, so new instances of the containing type should
 ///   have values for this field taken from the default sequence for
-///   the field type.
+///   the field type. Numbers, strings, standard collections, tuples,
+///   arrays and chrono's date and time types all implement
+///   `Generatable`, with sequences that vary from one instance to the
+///   next.
 ///
 /// - `#[boulder(generatable(a=Inc(3i32)))]` The type for this field
 ///   implements `Generatable`, and the generator for values for this
 ///   field should be customised, such that the nested field `a` uses
 ///   the generator `Inc(3i32)`. `Inc(3i32)` can be replaced by an
 ///   arbitrary expression which evaluates to a `Generator` instance.
+///   For either form, unless the field is a sequence, the generator
+///   also gets a `foo_with` method for a field `foo`, which takes a
+///   closure that further customises the nested generator, starting
+///   from `a=Inc(3i32)`.
 ///
 /// - `#[boulder(sequence_generator=Repeat(2usize, 3usize))]` This
 ///   field is assumed to be a collection type (a type which can be
@@ -125,12 +488,94 @@ pub fn builder(input: TokenStream) -> TokenStream {
 ///   `Repeat(2usize, 3usize)` can be replaced by an arbitrary
 ///   expression which evaluates to a `Generator`.
 ///
+/// - `#[boulder(recursive(max_depth=4, branching=Repeat!(0usize, 2usize, 3usize)))]`
+///   This field is a collection of values of a recursive type, for
+///   example `Vec<Box<Node>>` inside `Node`. The number of items is
+///   taken from `branching`, but shrinks with the depth of nesting,
+///   and is zero once `max_depth` is reached, so that generation
+///   terminates. If `branching` is omitted, the `sequence_generator`
+///   is used instead. The items are generated as for `generatable`,
+///   which this tag stacks with, as it does with `generator`. See
+///   `Recursive` for details.
+///
 /// The generator will additionally use all tags defined for
 /// `Buildable` if those specific to `Generatable` are not present. In
 /// this case, all instances in the sequence the generator produces
 /// will receive the same value for the given field. This includes the
 /// `sequence` tag.
 ///
+/// The `preset` tag described for `Buildable` is also supported,
+/// where each value becomes a generator producing that value every
+/// time. Preset values are converted as the builder's setter would
+/// convert them, so with `setter(strip_option)` a preset value for
+/// an `Option` field is wrapped in `Some`. Doc comments on a field
+/// are copied onto its generator setter.
+///
+/// Transient parameters are supported too, and set on the generator
+/// in the same way. A `default_from` field is recomputed for every
+/// instance, until its generator is replaced.
+///
+/// The `after_build` hooks described for `Buildable` run on every
+/// generated value too. Unlike a builder's, a generator's
+/// `.after_build(...)` closure may be `FnMut`, so it can keep state
+/// between values, and need not be `Send` or `Sync`. A generator
+/// already holds its field generators as `Box<dyn Generator>`, so it
+/// is never shared between threads, and it is neither cloned nor
+/// merged, so its hooks are owned rather than reference counted.
+///
+/// Generators also have `create(&mut repo)`, which generates a value
+/// and stores it as described for `Buildable`. The values of both
+/// `buildable` and `generatable` fields are stored first, which
+/// needs their types to implement `Persist`, as derived by
+/// `Buildable`.
+///
+/// A `default_async` field is awaited afresh for every instance by
+/// `AsyncGenerator::generate`, until its generator is replaced.
+/// As for builders, a generator with such a field only implements
+/// `AsyncGenerator`, and the type is `AsyncGeneratable` rather than
+/// `Generatable`.
+///
+/// By default, unattributed fields are default constructed in every
+/// instance. Placing `#[boulder(auto)]` on the struct itself gives
+/// each unattributed field a generator that varies instead, where its
+/// type is recognised: integers and floats count up from zero using
+/// `Inc`, a `String` field `name` yields `"name-0"`, `"name-1"`, and so
+/// on, `bool` alternates starting from `false`, and `Option` of any of
+/// these alternates between `Some` and `None` using `Maybe` with
+/// `Frequency::Every(2)`. Fields of other types, and fields with any
+/// `boulder` attribute, are unaffected.
+///
+/// ```rust
+/// use boulder::{Generatable, Generator};
+///
+/// #[derive(Generatable)]
+/// #[boulder(auto)]
+/// struct Account {
+///     id: u64,
+///     name: String,
+///     admin: bool,
+///     nickname: Option<String>,
+///     #[boulder(default = 5)]
+///     level: i32,
+/// }
+///
+/// let mut gen = Account::generator();
+/// let a = gen.generate();
+/// assert_eq!((a.id, a.name.as_str(), a.admin, a.nickname, a.level), (0, "name-0", false, Some("nickname-0".to_string()), 5));
+/// let a = gen.generate();
+/// assert_eq!(a.id, 1);
+/// assert_eq!(a.name, "name-1");
+/// assert!(a.admin);
+/// assert_eq!(a.nickname, None);
+/// assert_eq!(a.level, 5);
+/// ```
+///
+/// The `remote` and `constructor` tags described for `Buildable` are
+/// also supported, giving the mirror struct an inherent `generator()`
+/// method which produces values of the remote type. Use
+/// `#[boulder(generator=ConfigDef::generator())]` for fields of the
+/// remote type.
+///
 /// Example:
 /// ```rust
 /// use boulder::{Generatable, Generator, Inc};
@@ -236,6 +681,38 @@ pub fn repeat(input: TokenStream) -> TokenStream {
     repeat::repeat_macro(syn::parse_macro_input!(input)).into()
 }
 
+/// Make a `Generator` that chooses values with given relative
+/// frequencies.
+///
+/// Each argument is a value and a `u64` weight, separated by
+/// `=>`. As with `Repeat!`, the values are coerced to a common type
+/// with `into()`. By default the values are spread evenly and
+/// deterministically: in every window whose length is the sum of the
+/// weights, each value appears exactly as often as its weight. Call
+/// `.seeded(n)` on the result to choose randomly instead, with a
+/// reproducible sequence for each seed.
+///
+/// Example:
+/// ```rust
+/// use boulder::{Generatable, Generator, Weighted};
+///
+/// #[derive(Generatable)]
+/// struct Account {
+///    #[boulder(generator=Weighted!("active" => 90, "suspended" => 9, "deleted" => 1))]
+///    status: String
+/// }
+///
+/// let accounts: Vec<_> = Account::generator().into_iter().take(100).collect();
+/// let count = |s: &str| accounts.iter().filter(|a| a.status == s).count();
+/// assert_eq!(count("active"), 90);
+/// assert_eq!(count("suspended"), 9);
+/// assert_eq!(count("deleted"), 1);
+/// ```
+#[proc_macro]
+pub fn weighted(input: TokenStream) -> TokenStream {
+    weighted::weighted_macro(syn::parse_macro_input!(input)).into()
+}
+
 #[cfg(feature = "persian-rug")]
 mod persian_rug;
 
@@ -268,15 +745,21 @@ mod persian_rug;
 ///
 /// - `#[boulder(buildable(a=5, b=10))]` The type for this field implements
 ///   `Buildable`, and new instances should be customised from the
-///    default by setting `a=5` and `b=10` where `a` and `b` are
-///    member names, and `5` and `10` can be replaced by arbitrary
-///    well-formed Rust expressions.
+///   default by setting `a=5` and `b=10` where `a` and `b` are
+///   member names, and `5` and `10` can be replaced by arbitrary
+///   well-formed Rust expressions.
 ///
 /// - `#[boulder(buildable_with_persian_rug(a=5, b=10))]` The type for
 ///   this field implements `BuildableWithPersianRug`, and new
 ///   instances should be customised from the default by setting `a=5`
 ///   and `b=10` where `a` and `b` are member names, and `5` and `10`
-///   can be replaced by arbitrary well-formed Rust expressions.
+///   can be replaced by arbitrary well-formed Rust expressions. For
+///   either form of `buildable_with_persian_rug`, the builder also
+///   gets a `foo_with` method for a field `foo`, which takes a
+///   closure that further customises the nested builder, starting
+///   from `a=5` and `b=10`; the nested value is built when the outer
+///   `build(context)` is called. The closure must be `Send + Sync`,
+///   so it can't capture `Rc` or `RefCell` state.
 ///
 /// - `#[boulder(sequence=3)]` This field is assumed to be a
 ///   collection type (a type which can be the target of
@@ -286,7 +769,9 @@ mod persian_rug;
 ///   generator specification (`generator` or `generatable`) if one is
 ///   given; otherwise it will be from the builder specification
 ///   (`default` or `buildable`, as described above) if one is given;
-///   otherwise the items will be default initialized.
+///   otherwise the items will be default initialized. Arrays are
+///   supported too, in which case the number of items must match
+///   the array length.
 ///
 /// - `#[boulder(sequence_with_persian_rug=|context| {(f(context),
 ///   context)}]` This field is assumed to be a collection type (a
@@ -300,7 +785,46 @@ mod persian_rug;
 ///   will be from the builder specification (`default`, `buildable`,
 ///   `default_with_persian_rug` or `buildable_with_persian_rug` as
 ///   described above) if one is given; otherwise the items will be
-///   default initialized.
+///   default initialized. The collection setters that `Buildable`
+///   adds for sequence fields are not available, since the items
+///   are only made when the builder is built.
+///
+/// Named presets, `#[boulder(preset(admin, role="admin"))]`, can be
+/// declared on the struct as for `Buildable`.
+///
+/// Setters can be configured with `#[boulder(setter(strip_option,
+/// into = false, prefix = "with_"))]`, on fields or on the struct,
+/// as for `Buildable`.
+///
+/// Transient parameters, `#[boulder(transient(premium: bool))]`, and
+/// fields with `#[boulder(default_from = |t| ...)]` work as for
+/// `Buildable`; the closure doesn't receive the context. There are
+/// no asynchronous builders, so `default_async` is not supported.
+///
+/// The `after_build` hooks work as for `Buildable`, and run before
+/// the value is added to the context. Hooks which need the context
+/// itself, for example to add back-references to the new value, can
+/// be declared on the struct with
+/// `#[boulder(after_insert_with_persian_rug = path::to::fn)]`. The
+/// function takes the new `&Proxy<Foo>` and the `Mutator`, and
+/// returns the `Mutator`; it is usually generic over the `Mutator`
+/// type. It only runs when the result is a `Proxy<Foo>`, after the
+/// value has been added. This can be repeated, and the hooks run in
+/// order.
+///
+/// There is no `create(&mut repo)`, since the context already
+/// stores what is built.
+///
+/// `#[boulder(patch)]` is ignored, and there is no `apply`, `merge`,
+/// `build_ref` or `build_many`; the patch struct comes from deriving
+/// `Buildable`.
+///
+/// Builders can also start from an existing value, using
+/// `to_builder(&self)` or `Builder::from(foo)` as for
+/// `Buildable`. To copy a value held in a context into a new proxy,
+/// use `Builder::from_proxy(&proxy, &context)`, where `Builder` is
+/// the builder for `Proxy<Foo>`, or `value.to_proxy_builder()` if
+/// you already have the value.
 ///
 /// Example:
 /// ```rust
@@ -449,6 +973,19 @@ pub fn buildable_with_persian_rug(input: TokenStream) -> TokenStream {
 /// same value for the given field. This includes the `sequence` and
 /// `sequence_with_persian_rug` tags.
 ///
+/// `#[boulder(auto)]` on the struct itself is supported as for
+/// `Generatable`, giving unattributed fields of recognised types a
+/// varying generator. So is `#[boulder(preset(...))]`; since setting
+/// a field changes the type of the generator, the preset method
+/// returns a generator whose preset fields are boxed.
+///
+/// Transient parameters, `default_from` and `default_async` are not
+/// supported; give such a field a generator instead. The
+/// `after_build` and `after_insert_with_persian_rug` hooks are
+/// supported as for `BuildableWithPersianRug`.
+///
+/// As for `BuildableWithPersianRug`, there is no `create(&mut repo)`.
+///
 /// Example:
 /// ```rust
 /// use boulder::{GeneratableWithPersianRug, GeneratorWithPersianRug};
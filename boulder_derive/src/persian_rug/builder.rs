@@ -1,5 +1,6 @@
-use super::helpers::get_persian_rug_constraints;
+use super::helpers::{after_insert, after_insert_bound, get_persian_rug_constraints};
 use proc_macro2 as pm2;
+use syn::ext::IdentExt;
 
 use crate::attributes::*;
 
@@ -27,6 +28,14 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
     let mut change_type_body = pm2::TokenStream::new();
     let mut defaults = pm2::TokenStream::new();
     let mut dyn_generators = pm2::TokenStream::new();
+    let mut from_body = pm2::TokenStream::new();
+    let mut fieldids = Vec::new();
+    let mut field_patterns = Vec::new();
+    let mut clone_body = pm2::TokenStream::new();
+    let mut setters = std::collections::HashMap::new();
+    let mut fieldtypes = Vec::new();
+    let mut needs_static = false;
+    let type_params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
 
     let (context, used_types) = match get_persian_rug_constraints(&attrs) {
         Ok(context) => context,
@@ -35,6 +44,18 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
         }
     };
 
+    let TypeMeta {
+        presets,
+        setter: setter_defaults,
+        transients,
+        after_build: after_build_hooks,
+        after_insert: after_insert_hooks,
+        ..
+    } = match TypeMeta::from_attributes(&attrs) {
+        Ok(meta) => meta,
+        Err(e) => return e.to_compile_error(),
+    };
+
     let mut constraints = pm2::TokenStream::new();
     constraints.extend(quote::quote! {
         context = #context,
@@ -53,12 +74,17 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
             for field in named.iter() {
                 let fieldid = field.ident.as_ref().unwrap();
                 let fieldtype = &field.ty;
+                fieldids.push(fieldid.clone());
+                fieldtypes.push(fieldtype.clone());
                 let mut builder = BuildType::Default;
                 let mut generator = GeneratorType::Default;
                 let mut sequence = None;
                 let mut builder_needs_context = false;
                 let mut generator_needs_context = false;
                 let mut sequence_needs_context = false;
+                let with_id = quote::format_ident!("_boulder_with_{}", fieldid.unraw());
+                let with_method = quote::format_ident!("{}_with", fieldid.unraw());
+                let mut clear_with = pm2::TokenStream::new();
 
                 for attr in field.attrs.iter() {
                     if attr.path.is_ident("boulder") {
@@ -81,6 +107,12 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                     }
                 }
 
+                let setter = match SetterOptions::setter(&setter_defaults, field) {
+                    Ok(setter) => setter,
+                    Err(e) => return e.to_compile_error(),
+                };
+                let cfg = &setter.cfg;
+
                 if let Some(sequence) = sequence {
                     let mut gen_init = pm2::TokenStream::new();
                     let needs_context;
@@ -159,6 +191,12 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                                         });
                                     }
                                 }
+                                BuildType::DefaultFrom(_) => {
+                                    unreachable!("default_from is rejected for sequences")
+                                }
+                                BuildType::DefaultAsync(_) => {
+                                    unreachable!("default_async is rejected by the parser")
+                                }
                                 BuildType::Default => {
                                     static_value.extend(quote::quote! {
                                         Default::default()
@@ -171,10 +209,12 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
 
                                 dyn_generators.extend(quote::quote! {
                                     // This is just so the generics are used and in scope for #fieldtype
+                                    #cfg
                                     struct #new_generator_id #generics #wc {
                                         _marker: core::marker::PhantomData<#ident #ty_generics>
                                     }
 
+                                    #cfg
                                     #[persian_rug::constraints(#constraints)]
                                     impl #generics ::boulder::GeneratorWithPersianRug<#context> for #new_generator_id #ty_generics #wc
                                     {
@@ -215,8 +255,10 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                     };
 
                     if needs_context {
+                        let collected =
+                            collect_sequence(fieldtype, quote::quote! { storage.into_iter() });
                         defaults.extend(quote::quote! {
-                            let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
+                            #cfg let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
                                 (value, context)
                             } else {
                                 #sequence
@@ -227,19 +269,21 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                                     storage.push(iter.next().unwrap());
                                 }
                                 let (gen, context) = iter.into_inner();
-                                let value = storage.into_iter().collect();
+                                let value = #collected;
                                 (value, context)
                             };
                         });
                     } else {
+                        let collected =
+                            collect_sequence(fieldtype, quote::quote! { iter.take(count) });
                         defaults.extend(quote::quote! {
-                            let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
+                            #cfg let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
                                 (value, context)
                             } else {
                                 #sequence
                                 let mut gen = #gen_init;
                                 let iter = ::boulder::GeneratorIterator::new(gen);
-                                let value = iter.take(count).collect();
+                                let value = #collected;
                                 (value, context)
                             };
                         });
@@ -257,14 +301,49 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                                         .#k(#v)
                                     });
                                 }
+                                let nested_builder = quote::quote! {
+                                    <#fieldtype as ::boulder::BuildableWithPersianRug<#context>>::Builder
+                                };
                                 defaults.extend(quote::quote! {
-                                    let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
+                                    #cfg let (#fieldid, mut context) = if let Some(value) = self.#fieldid {
                                         (value, context)
                                     } else {
-                                        <<#fieldtype as ::boulder::BuildableWithPersianRug<#context>>::Builder as ::boulder::BuilderWithPersianRug<#context>>::build(#initializers, context)
-
+                                        let builder = #initializers;
+                                        let builder = match &self.#with_id {
+                                            Some(f) => f.customise(builder),
+                                            None => builder,
+                                        };
+                                        <#nested_builder as ::boulder::BuilderWithPersianRug<#context>>::build(builder, context)
                                     };
                                 });
+                                body.extend(quote::quote! {
+                                    #cfg #with_id: Option<::std::sync::Arc<dyn ::boulder::guts::persian_rug::builder::Customisation<#context, #fieldtype>>>,
+                                });
+                                default_body.extend(quote::quote! {
+                                    #cfg #with_id: ::std::default::Default::default(),
+                                });
+                                change_type_body.extend(quote::quote! {
+                                    #cfg #with_id: self.#with_id,
+                                });
+                                from_body.extend(quote::quote! {
+                                    #cfg #with_id: ::std::default::Default::default(),
+                                });
+                                clear_with.extend(quote::quote! {
+                                    self.#with_id = None;
+                                });
+                                needs_static = true;
+                                methods.extend(quote::quote! {
+                                    #cfg
+                                    pub fn #with_method<F>(mut self, f: F) -> Self
+                                    where
+                                        F: Fn(#nested_builder) -> #nested_builder + Send + Sync + 'static,
+                                        #(#type_params: 'static,)*
+                                    {
+                                        self.#fieldid = None;
+                                        self.#with_id = Some(::std::sync::Arc::new(f));
+                                        self
+                                    }
+                                });
                             } else {
                                 let mut initializers = pm2::TokenStream::new();
                                 initializers.extend(quote::quote! {
@@ -276,7 +355,7 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                                     });
                                 }
                                 defaults.extend(quote::quote! {
-                                    let #fieldid = if let Some(value) = self.#fieldid {
+                                    #cfg let #fieldid = if let Some(value) = self.#fieldid {
                                         value
                                     } else {
                                         <<#fieldtype as ::boulder::Buildable>::Builder as ::boulder::Builder>::build(#initializers)
@@ -287,7 +366,7 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                         BuildType::Value { expr: value, .. } => {
                             if builder_needs_context {
                                 defaults.extend(quote::quote! {
-                                    let (#fieldid, context) = if let Some(value) = self.#fieldid {
+                                    #cfg let (#fieldid, context) = if let Some(value) = self.#fieldid {
                                         (value, context)
                                     } else {
                                         let closure: fn(BoulderMutatorParam) -> (#fieldtype, BoulderMutatorParam) = { #value };
@@ -297,40 +376,78 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                                 });
                             } else {
                                 defaults.extend(quote::quote! {
-                                    let #fieldid = self.#fieldid.unwrap_or_else(|| (#value).into());
+                                    #cfg let #fieldid = self.#fieldid.unwrap_or_else(|| (#value).into());
                                 });
                             }
                         }
+                        BuildType::DefaultAsync(_) => {
+                            unreachable!("default_async is rejected by the parser")
+                        }
+                        BuildType::DefaultFrom(expr) => {
+                            let default = default_from(
+                                &expr,
+                                fieldtype,
+                                quote::quote! { &self._boulder_transient },
+                            );
+                            defaults.extend(quote::quote! {
+                                #cfg let #fieldid = self.#fieldid.unwrap_or_else(|| #default);
+                            });
+                        }
                         BuildType::Default => {
                             defaults.extend(quote::quote! {
-                                let #fieldid = self.#fieldid.unwrap_or_default();
+                                #cfg let #fieldid = self.#fieldid.unwrap_or_default();
                             });
                         }
                     };
                 }
 
                 body.extend(quote::quote! {
-                    #fieldid: Option<#fieldtype>,
+                    #cfg #fieldid: Option<#fieldtype>,
                 });
                 default_body.extend(quote::quote! {
-                    #fieldid: ::std::default::Default::default(),
+                    #cfg #fieldid: ::std::default::Default::default(),
                 });
-                methods.extend(quote::quote! {
-                    pub fn #fieldid<BoulderFunctionParam>(mut self, value: BoulderFunctionParam) -> Self
-                    where
-                        BoulderFunctionParam: Into<#fieldtype>
-                    {
-                        self.#fieldid = Some(value.into());
-                        self
+                methods.extend(setter.methods(|value| {
+                    quote::quote! {
+                        self.#fieldid = Some(#value);
+                        #clear_with
                     }
-                });
+                }));
                 make_body.extend(quote::quote! {
-                    #fieldid,
+                    #cfg #fieldid,
+                });
+                from_body.extend(quote::quote! {
+                    #cfg #fieldid: Some(#fieldid),
                 });
                 change_type_body.extend(quote::quote! {
-                    #fieldid: self.#fieldid,
+                    #cfg #fieldid: self.#fieldid,
+                });
+                field_patterns.push(quote::quote! { #cfg #fieldid });
+                clone_body.extend(quote::quote! {
+                    #cfg #fieldid: ::core::clone::Clone::clone(&self.#fieldid),
+                });
+                setters.insert(fieldid.clone(), setter);
+            }
+
+            for preset in &presets {
+                let name = &preset.name;
+                let chain = match preset.apply(&named, quote::quote! { self }, |field, value| {
+                    Ok(setters[field.ident.as_ref().unwrap()].call(value))
+                }) {
+                    Ok(chain) => chain,
+                    Err(e) => return e.to_compile_error(),
+                };
+                methods.extend(quote::quote! {
+                    pub fn #name(self) -> Self {
+                        #chain
+                    }
                 });
             }
+
+            match transients.methods(&named, false) {
+                Ok(transient_methods) => methods.extend(transient_methods),
+                Err(e) => return e.to_compile_error(),
+            }
         }
     }
 
@@ -375,10 +492,43 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
         }
     };
 
+    // A `_with` customisation is stored as an `Arc<dyn Customisation>`
+    // closure over the nested builder, which must be 'static; that
+    // holds if all the type parameters are.
+    let mut build_generics = full_generics.clone();
+    if needs_static {
+        let params: Vec<_> = build_generics
+            .type_params()
+            .map(|p| p.ident.clone())
+            .collect();
+        let wc = build_generics.make_where_clause();
+        for param in params {
+            wc.predicates.push(syn::parse_quote! { #param: 'static });
+        }
+    }
+    let build_wc = &build_generics.where_clause;
+
+    let transient_definition = transients.definition();
+
+    let target = quote::quote! { #ident #ty_generics };
+    let run_hooks = after_build(
+        &after_build_hooks,
+        target.clone(),
+        quote::quote! { value },
+        quote::quote! { self._boulder_after_build.iter() },
+    );
+    let wrapper = quote::quote! { BoulderExtraGenericParam };
+    let run_insert_hooks = after_insert(&after_insert_hooks, &target, &wrapper);
+    let insert_bound = after_insert_bound(&after_insert_hooks, &target, &wrapper);
+
     let res = quote::quote! {
         const _: () = {
+            #transient_definition
+
             #vis struct Builder<BoulderTypeMarkerParam #bare_generics> #wc {
                 _boulder_created_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
+                _boulder_transient: BoulderTransients,
+                _boulder_after_build: ::std::vec::Vec<::std::sync::Arc<dyn Fn(&mut #target) + Send + Sync>>,
                 #body
             }
 
@@ -390,277 +540,136 @@ pub fn derive_buildable_with_persian_rug(input: syn::DeriveInput) -> pm2::TokenS
                 pub fn new() -> Self {
                     Self {
                         _boulder_created_marker: Default::default(),
+                        _boulder_transient: BoulderTransients::new(),
+                        _boulder_after_build: ::std::vec::Vec::new(),
                         #default_body
                     }
                 }
 
+                /// Create a builder which starts from a copy of the
+                /// value behind `proxy` in `context`, rather than from
+                /// the defaults.
+                pub fn from_proxy<BoulderAccessorParam>(proxy: &::persian_rug::Proxy<#ident #ty_generics>, context: BoulderAccessorParam) -> Self
+                where
+                    BoulderAccessorParam: ::persian_rug::Accessor<Context = #context>,
+                    #context: ::persian_rug::Owner<#ident #ty_generics>,
+                    #ident #ty_generics: ::persian_rug::Contextual<Context = #context>,
+                    #(for<'boulder_clone> #fieldtypes: ::core::clone::Clone,)*
+                {
+                    context.get(proxy).to_builder().change_type()
+                }
+
                 fn change_type<BoulderFunctionTypeParam>(self) -> Builder<BoulderFunctionTypeParam #bare_ty_generics> {
                     Builder {
                         _boulder_created_marker: Default::default(),
+                        _boulder_transient: self._boulder_transient,
+                        _boulder_after_build: self._boulder_after_build,
                         #change_type_body
                     }
                 }
 
-                #methods
-            }
-
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl #generics ::boulder::guts::persian_rug::builder::BoulderBase for #ident #ty_generics #wc {
-                type Base = #ident #ty_generics;
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl #generics ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for #ident #ty_generics #wc {
-                type Builder = Builder<#ident #ty_generics #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl #generics ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<#ident #ty_generics #bare_ty_generics> #wc
-            {
-                type Result = #ident #ty_generics;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
-                {
-                    #defaults
-
-                    (
-                        #ident {
-                            #make_body
-                        },
-                        context
-                    )
-                }
-            }
-
-            // Option
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for Option<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<Option<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<Option<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = Option<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
+                /// Run `hook` on each value this builder builds, before
+                /// it is added to the context, and after any hooks added
+                /// earlier.
+                pub fn after_build<BoulderFunctionParam>(mut self, hook: BoulderFunctionParam) -> Self
                 where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
+                    BoulderFunctionParam: Fn(&mut #target) + Send + Sync + 'static
                 {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (Some(result), context)
-                }
-            }
-
-            // Proxy
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints, access(BoulderExtraGenericParam))]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::persian_rug::Proxy<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::persian_rug::Proxy<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+                    self._boulder_after_build.push(::std::sync::Arc::new(hook));
+                    self
                 }
-            }
 
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints, access(BoulderExtraGenericParam))]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::persian_rug::Proxy<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::persian_rug::Proxy<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
-                {
-                    let (result, mut context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (context.add(result), context)
-                }
+                #methods
             }
 
-            // Arc
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::std::sync::Arc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
-            }
 
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
+            impl #generics ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<#ident #ty_generics #bare_ty_generics> #build_wc
             {
-                type Result = ::std::sync::Arc<BoulderExtraGenericParam>;
+                type Result = #ident #ty_generics;
                 fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
                 where
                     BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
                 {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (::std::sync::Arc::new(result), context)
-                }
-            }
-
-            // Rc
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::std::rc::Rc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
-            }
+                    #defaults
 
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::rc::Rc<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
-                {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (::std::rc::Rc::new(result), context)
+                    let mut value = #ident {
+                        #make_body
+                    };
+                    #run_hooks
+                    (value, context)
                 }
             }
 
-            // Cell
             #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::std::cell::Cell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
+            impl<BoulderTypeMarkerParam #bare_generics> ::core::convert::From<#ident #ty_generics> for Builder<BoulderTypeMarkerParam #bare_ty_generics> #wc {
+                fn from(value: #ident #ty_generics) -> Self {
+                    let #ident { #(#field_patterns),* } = value;
+                    Self {
+                        _boulder_created_marker: Default::default(),
+                        _boulder_transient: BoulderTransients::new(),
+                        _boulder_after_build: ::std::vec::Vec::new(),
+                        #from_body
+                    }
                 }
             }
 
             #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::cell::Cell<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
+            impl #generics #ident #ty_generics #wc {
+                /// Create a builder which starts from a copy of this
+                /// value, rather than from the defaults.
+                #vis fn to_builder(&self) -> Builder<#ident #ty_generics #bare_ty_generics>
                 where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
+                    // The bounds are higher-ranked so that they are
+                    // only checked when this method is used.
+                    #(for<'boulder_clone> #fieldtypes: ::core::clone::Clone,)*
                 {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (::std::cell::Cell::new(result), context)
+                    Builder::from(#ident {
+                        #clone_body
+                    })
                 }
-            }
 
-            // RefCell
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::std::cell::RefCell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
-                    Builder::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics>
-            where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Result = ::std::cell::RefCell<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
+                /// Create a builder for a new proxy, which starts from
+                /// a copy of this value rather than from the defaults.
+                #vis fn to_proxy_builder(&self) -> Builder<::persian_rug::Proxy<#ident #ty_generics> #bare_ty_generics>
                 where
-                    BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
+                    #(for<'boulder_clone> #fieldtypes: ::core::clone::Clone,)*
                 {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (::std::cell::RefCell::new(result), context)
+                    Builder::from(#ident {
+                        #clone_body
+                    })
                 }
             }
 
-            // Mutex
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context> for ::std::sync::Mutex<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::builder::MiniBuildableWithPersianRug<#ident #ty_generics, #context>,
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
-                #bare_wc
-            {
-                type Builder = Builder<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>;
-                fn mini_builder() -> Self::Builder {
+            impl #generics ::boulder::BuildableBaseWithPersianRug for #ident #ty_generics #wc {
+                type Builder<BoulderExtraGenericParam> = Builder<BoulderExtraGenericParam #bare_ty_generics>;
+                fn builder_for<BoulderExtraGenericParam>() -> Self::Builder<BoulderExtraGenericParam> {
                     Builder::new()
                 }
             }
 
+            // Wrappers
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics>
+            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context> for Builder<BoulderExtraGenericParam #bare_ty_generics>
             where
-                Builder<BoulderExtraGenericParam #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=BoulderExtraGenericParam>,
+                BoulderExtraGenericParam: ::boulder::WrapperWithPersianRug + ::boulder::WrapWithPersianRug<#context>,
+                Builder<<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner #bare_ty_generics>: ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context, Result=<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner>,
+                #insert_bound
                 #bare_wc
             {
-                type Result = ::std::sync::Mutex<BoulderExtraGenericParam>;
-                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, mut context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
+                type Result = BoulderExtraGenericParam;
+                fn build<'boulder_mutator_lifetime, BoulderMutatorParam>(self, context: BoulderMutatorParam) -> (Self::Result, BoulderMutatorParam)
                 where
                     BoulderMutatorParam: 'boulder_mutator_lifetime + ::persian_rug::Mutator<Context=#context>
                 {
-                    let (result, context) = <Builder<BoulderExtraGenericParam #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
-                    (::std::sync::Mutex::new(result), context)
+                    let (result, context) = <Builder<<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner #bare_ty_generics> as ::boulder::guts::persian_rug::builder::MiniBuilderWithPersianRug<#context>>::build(self.change_type(), context);
+                    let (result, context) = <BoulderExtraGenericParam as ::boulder::WrapWithPersianRug<#context>>::wrap::<'boulder_mutator_lifetime, BoulderMutatorParam>(result, context);
+                    #run_insert_hooks
+                    (result, context)
                 }
             }
         };
@@ -7,7 +7,7 @@
 // - Customisation depends on impl Trait RFC 2515
 // - Types can be removed when RFC 2515 lands
 
-use super::helpers::get_persian_rug_constraints;
+use super::helpers::{after_insert, after_insert_bound, get_persian_rug_constraints};
 use proc_macro2 as pm2;
 
 use crate::attributes::*;
@@ -93,6 +93,18 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
         }
     };
 
+    let TypeMeta {
+        auto,
+        presets,
+        setter: setter_defaults,
+        after_build: after_build_hooks,
+        after_insert: after_insert_hooks,
+        ..
+    } = match TypeMeta::from_attributes(&attrs) {
+        Ok(meta) => meta,
+        Err(e) => return e.to_compile_error(),
+    };
+
     let mut constraints = pm2::TokenStream::new();
     constraints.extend(quote::quote! {
         context = #context,
@@ -189,6 +201,20 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                     }
                 }
 
+                if auto
+                    && matches!(builder, BuildType::Default)
+                    && matches!(generator, GeneratorType::Default)
+                    && sequence.is_none()
+                    && build_sequence.is_none()
+                {
+                    if let Some(expr) = auto_generator(fieldid, fieldtype) {
+                        generator = GeneratorType::Generator {
+                            expr: Box::new(expr),
+                            ty: None,
+                        };
+                    }
+                }
+
                 let new_generic_arg = make_generic_id_for_field(field);
 
                 body.extend(quote::quote! {
@@ -219,7 +245,9 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                     },
                 );
 
+                let docs = forwarded_attributes(field, &["doc"]);
                 methods.extend(quote::quote! {
+                    #docs
                     pub fn #fieldid<BoulderFieldTypeParam>(self, generator: BoulderFieldTypeParam) -> Generator<BoulderTypeMarkerParam #bare_ty_generics #method_generics>
                     where
                         BoulderFieldTypeParam: ::boulder::GeneratorWithPersianRug<#context, Output=#fieldtype>
@@ -227,6 +255,7 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                         Generator {
                             _boulder_type_marker: Default::default(),
                             _boulder_type_storage: Default::default(),
+                            _boulder_after_build: self._boulder_after_build,
                             #method_body
                         }
                     }
@@ -347,6 +376,16 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                                     });
                                 }
                             }
+                            BuildType::DefaultAsync(_) => {
+                                unreachable!("default_async is rejected by the parser")
+                            }
+                            BuildType::DefaultFrom(_) => {
+                                return syn::Error::new_spanned(
+                                    fieldid,
+                                    "default_from is not supported by GeneratableWithPersianRug; give this field a generator",
+                                )
+                                .to_compile_error();
+                            }
                             BuildType::Default => {
                                 static_value.extend(quote::quote! {
                                     Default::default()
@@ -472,16 +511,95 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                     });
                 }
             }
+
+            for preset in &presets {
+                let name = &preset.name;
+                let chain = match preset.apply(&named, quote::quote! { self }, |field, value| {
+                    let (name, ty) = (&field.ident, &field.ty);
+                    let value = SetterOptions::setter(&setter_defaults, field)?.preset_value(value);
+                    Ok(quote::quote! {
+                        .#name(::boulder::GeneratorToGeneratorWithPersianRugWrapper::new(
+                            || -> #ty { #value }
+                        ))
+                    })
+                }) {
+                    Ok(chain) => chain,
+                    Err(e) => return e.to_compile_error(),
+                };
+                // Each setter changes the type of the generator, so
+                // the preset fields end up boxed.
+                let mut preset_generics = pm2::TokenStream::new();
+                for field in named.iter() {
+                    if preset
+                        .values
+                        .iter()
+                        .any(|(k, _)| field.ident.as_ref() == Some(k))
+                    {
+                        let fieldtype = &field.ty;
+                        preset_generics.extend(quote::quote! {
+                            , ::boulder::GeneratorToGeneratorWithPersianRugWrapper<#fieldtype>
+                        });
+                    } else {
+                        let generic = make_generic_id_for_field(field);
+                        preset_generics.extend(quote::quote! {
+                            , #generic
+                        });
+                    }
+                }
+                methods.extend(quote::quote! {
+                    pub fn #name(self) -> Generator<BoulderTypeMarkerParam #bare_ty_generics #preset_generics> {
+                        #chain
+                    }
+                });
+            }
         }
     }
 
+    let nested_args = {
+        let mut res = pm2::TokenStream::new();
+        for p in &gen_generics.params {
+            match p {
+                syn::GenericParam::Type(syn::TypeParam { ident, .. }) => {
+                    res.extend(quote::quote! {
+                        #ident,
+                    });
+                }
+                syn::GenericParam::Lifetime(syn::LifetimeDef { lifetime, .. }) => {
+                    res.extend(quote::quote! {
+                        #lifetime,
+                    });
+                }
+                syn::GenericParam::Const(syn::ConstParam { ident, .. }) => {
+                    res.extend(quote::quote! {
+                        #ident,
+                    });
+                }
+            }
+        }
+        res
+    };
+
     let (gen_generics, gen_ty_generics, gen_wc) = gen_generics.split_for_impl();
 
+    let target = quote::quote! { #ident #ty_generics };
+    let run_hooks = after_build(
+        &after_build_hooks,
+        target.clone(),
+        quote::quote! { value },
+        quote::quote! { gen._boulder_after_build.iter_mut() },
+    );
+    let wrapper = quote::quote! { BoulderExtraGenericParam };
+    let run_insert_hooks = after_insert(&after_insert_hooks, &target, &wrapper);
+    let insert_bound = after_insert_bound(&after_insert_hooks, &target, &wrapper);
+
     let res = quote::quote! {
         const _: () = {
             #vis struct Generator<BoulderTypeMarkerParam #bare_generics #added_generics> #wc {
                 _boulder_type_marker: ::core::marker::PhantomData<BoulderTypeMarkerParam>,
                 _boulder_type_storage: ::core::marker::PhantomData<#ident #ty_generics>,
+                // Unlike a builder, a generator is neither cloned nor
+                // sent between threads, so its hooks can be stateful.
+                _boulder_after_build: ::std::vec::Vec<Box<dyn FnMut(&mut #target)>>,
                 #body
             }
 
@@ -504,6 +622,7 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                     Self {
                         _boulder_type_marker: Default::default(),
                         _boulder_type_storage: Default::default(),
+                        _boulder_after_build: ::std::vec::Vec::new(),
                         #default_values
                     }
                 }
@@ -513,6 +632,17 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
             #[persian_rug::constraints(#constraints)]
             impl<BoulderTypeMarkerParam #bare_generics #added_generics> Generator <BoulderTypeMarkerParam #bare_ty_generics #added_generics> #gen_wc
             {
+                /// Run `hook` on each value this generator generates,
+                /// before it is added to the context, and after any
+                /// hooks added earlier.
+                pub fn after_build<BoulderFunctionParam>(mut self, hook: BoulderFunctionParam) -> Self
+                where
+                    BoulderFunctionParam: FnMut(&mut #target) + 'static
+                {
+                    self._boulder_after_build.push(Box::new(hook));
+                    self
+                }
+
                 #methods
             }
 
@@ -537,27 +667,10 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                 }
             }
 
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl #generics ::boulder::guts::persian_rug::generator::BoulderBase for #ident #ty_generics #wc {
-                type Base = #ident #ty_generics;
-            }
-
             #dyn_generators
 
             // Base case
 
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl #generics ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for #ident #ty_generics #wc
-
-            {
-                type Generator = Generator<#ident #ty_generics #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-             }
-
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
             impl #gen_generics NestedGenerate #gen_ty_generics for Generator<#ident #ty_generics #bare_ty_generics #added_generics> #gen_wc
@@ -571,251 +684,44 @@ pub fn derive_generatable_with_persian_rug(input: syn::DeriveInput) -> pm2::Toke
                     BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context=#context>
                 {
                     #vars
-                    (
-                        #ident {
-                            #make_body
-                        },
-                        context
-                    )
-                }
-            }
-
-            // Option
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for Option<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<Option<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=Option<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<Option<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<Option<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = Option<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (Some(result), context)
-                }
-            }
-
-            // Proxy
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints, access(BoulderExtraGenericParam))]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::persian_rug::Proxy<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::persian_rug::Proxy<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::persian_rug::Proxy<BoulderExtraGenericParam>>,
-
-                #bare_wc
-            {
-                type Generator = Generator<::persian_rug::Proxy<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints, access(<Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output))]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::persian_rug::Proxy<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = ::persian_rug::Proxy<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>,
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (context.add(result), context)
-                }
-            }
-
-            // Arc
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::std::sync::Arc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::std::sync::Arc<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::std::sync::Arc<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = ::std::sync::Arc<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (::std::sync::Arc::new(result), context)
-                }
-            }
-
-            // Rc
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::std::rc::Rc<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::std::rc::Rc<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::std::rc::Rc<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = ::std::rc::Rc<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (::std::rc::Rc::new(result), context)
+                    let mut value = #ident {
+                        #make_body
+                    };
+                    #run_hooks
+                    (value, context)
                 }
             }
 
-            // Cell
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::std::cell::Cell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::std::cell::Cell<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::std::cell::Cell<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = ::std::cell::Cell<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (::std::cell::Cell::new(result), context)
-                }
-            }
-
-            // RefCell
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::std::cell::RefCell<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::std::cell::RefCell<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
-                    Generator::new()
-                }
-            }
-
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::std::cell::RefCell<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
-            where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
-            #added_wc
-            #bare_wc
-
-            {
-                type Output = ::std::cell::RefCell<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
-                where
-                    BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
-                {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (::std::cell::RefCell::new(result), context)
-                }
-            }
-
-            // Mutex
-            #[automatically_derived]
-            #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_generics> ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context> for ::std::sync::Mutex<BoulderExtraGenericParam>
-            where
-                BoulderExtraGenericParam: ::boulder::guts::persian_rug::generator::MiniGeneratableWithPersianRug<#ident #ty_generics, #context>,
-                Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics #default_types>: ::boulder::guts::persian_rug::generator::MiniGeneratorWithPersianRug<#context, Output=::std::sync::Mutex<BoulderExtraGenericParam>>,
-                #bare_wc
-            {
-                type Generator = Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics #default_types>;
-                fn mini_generator() -> Self::Generator {
+            impl #generics ::boulder::GeneratableBaseWithPersianRug for #ident #ty_generics #wc {
+                type Generator<BoulderExtraGenericParam> = Generator<BoulderExtraGenericParam #bare_ty_generics #default_types>;
+                fn generator_for<BoulderExtraGenericParam>() -> Self::Generator<BoulderExtraGenericParam> {
                     Generator::new()
                 }
             }
 
+            // Wrappers
             #[automatically_derived]
             #[persian_rug::constraints(#constraints)]
-            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<::std::sync::Mutex<BoulderExtraGenericParam> #bare_ty_generics #added_generics>
+            impl <BoulderExtraGenericParam #bare_ty_generics #added_generics> NestedGenerate #gen_ty_generics for Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>
             where
-                Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics>: NestedGenerate #gen_ty_generics,
+                BoulderExtraGenericParam: ::boulder::WrapperWithPersianRug + ::boulder::WrapWithPersianRug<#context>,
+                Generator<<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner #bare_ty_generics #added_generics>: NestedGenerate<#nested_args Output=<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner>,
+                #insert_bound
             #added_wc
             #bare_wc
 
             {
-                type Output = ::std::sync::Mutex<<Generator<BoulderExtraGenericParam #bare_ty_generics #added_generics> as NestedGenerate #gen_ty_generics>::Output>;
-                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, mut context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
+                type Output = BoulderExtraGenericParam;
+                fn nested_generate<'boulder_lifetime_param, BoulderMutatorParam, BoulderFunctionParam>(gen: &mut Generator<BoulderFunctionParam #bare_ty_generics #added_generics>, context: BoulderMutatorParam) -> (Self::Output, BoulderMutatorParam)
                 where
                     BoulderMutatorParam: 'boulder_lifetime_param + ::persian_rug::Mutator<Context = #context>
                 {
-                    let (result, mut context) = Generator::<BoulderExtraGenericParam #bare_ty_generics #added_generics>::nested_generate(gen, context);
-                    (::std::sync::Mutex::new(result), context)
+                    let (result, context) = Generator::<<BoulderExtraGenericParam as ::boulder::WrapperWithPersianRug>::Inner #bare_ty_generics #added_generics>::nested_generate(gen, context);
+                    let (result, context) = <BoulderExtraGenericParam as ::boulder::WrapWithPersianRug<#context>>::wrap::<'boulder_lifetime_param, BoulderMutatorParam>(result, context);
+                    #run_insert_hooks
+                    (result, context)
                 }
             }
 
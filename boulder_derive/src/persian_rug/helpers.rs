@@ -78,12 +78,31 @@ impl quote::ToTokens for ConstraintItem {
 
 // This doesn't really belong so deep in the tree
 /// Top level attributes for the boulder derives
+#[allow(dead_code)]
 pub enum BoulderTypeAttr {
     PersianRugConstraints {
         persian_rug: syn::Ident,
         paren: syn::token::Paren,
         constraints: syn::punctuated::Punctuated<ConstraintItem, syn::Token![,]>,
     },
+    Auto {
+        auto: syn::Ident,
+    },
+    Preset {
+        preset: syn::Ident,
+    },
+    Setter {
+        setter: syn::Ident,
+    },
+    Transient {
+        transient: syn::Ident,
+    },
+    AfterBuild {
+        after_build: syn::Ident,
+    },
+    Patch {
+        patch: syn::Ident,
+    },
 }
 
 impl syn::parse::Parse for BoulderTypeAttr {
@@ -100,6 +119,42 @@ impl syn::parse::Parse for BoulderTypeAttr {
                     constraints: punc,
                 })
             }
+            "auto" => Ok(BoulderTypeAttr::Auto { auto: attr }),
+            "preset" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                let _: pm2::TokenStream = content.parse()?;
+                Ok(BoulderTypeAttr::Preset { preset: attr })
+            }
+            "setter" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                let _: pm2::TokenStream = content.parse()?;
+                Ok(BoulderTypeAttr::Setter { setter: attr })
+            }
+            "transient" => {
+                let content;
+                let _: syn::token::Paren = syn::parenthesized!(content in input);
+                let _: pm2::TokenStream = content.parse()?;
+                Ok(BoulderTypeAttr::Transient { transient: attr })
+            }
+            "after_build" | "after_insert_with_persian_rug" => {
+                let _: syn::Token![=] = input.parse()?;
+                let _: syn::Expr = input.parse()?;
+                Ok(BoulderTypeAttr::AfterBuild { after_build: attr })
+            }
+            "patch" => {
+                if input.peek(syn::token::Paren) {
+                    let content;
+                    let _: syn::token::Paren = syn::parenthesized!(content in input);
+                    let _: pm2::TokenStream = content.parse()?;
+                }
+                Ok(BoulderTypeAttr::Patch { patch: attr })
+            }
+            "remote" | "constructor" => Err(syn::Error::new_spanned(
+                &attr,
+                format!("Cannot use {} inside a persian-rug enabled derive.", attr),
+            )),
             _ => Err(syn::Error::new_spanned(
                 attr,
                 "unsupported boulder attribute",
@@ -134,6 +189,12 @@ pub fn get_persian_rug_constraints(
                             }
                         }
                     }
+                    BoulderTypeAttr::Auto { .. }
+                    | BoulderTypeAttr::Preset { .. }
+                    | BoulderTypeAttr::Setter { .. }
+                    | BoulderTypeAttr::Transient { .. }
+                    | BoulderTypeAttr::AfterBuild { .. }
+                    | BoulderTypeAttr::Patch { .. } => {}
                 }
             }
         }
@@ -143,3 +204,43 @@ pub fn get_persian_rug_constraints(
         used_types,
     ))
 }
+
+/// Run the struct's `after_insert_with_persian_rug` hooks, if the
+/// freshly wrapped `result`, of type `wrapper`, is a proxy for a
+/// value of type `ty` that was just added to `context`.
+pub fn after_insert(
+    hooks: &[syn::Expr],
+    ty: &pm2::TokenStream,
+    wrapper: &pm2::TokenStream,
+) -> pm2::TokenStream {
+    if hooks.is_empty() {
+        return pm2::TokenStream::new();
+    }
+    quote::quote! {
+        let context = match <#wrapper as ::boulder::InsertedWithPersianRug<#ty>>::inserted(&result) {
+            Some(proxy) => {
+                let mut context = context;
+                #(
+                    let hook: fn(&::persian_rug::Proxy<#ty>, BoulderMutatorParam) -> BoulderMutatorParam = #hooks;
+                    context = hook(proxy, context);
+                )*
+                context
+            }
+            None => context,
+        };
+    }
+}
+
+/// The bound needed by [`after_insert`], if there are any hooks.
+pub fn after_insert_bound(
+    hooks: &[syn::Expr],
+    ty: &pm2::TokenStream,
+    wrapper: &pm2::TokenStream,
+) -> pm2::TokenStream {
+    if hooks.is_empty() {
+        return pm2::TokenStream::new();
+    }
+    quote::quote! {
+        #wrapper: ::boulder::InsertedWithPersianRug<#ty>,
+    }
+}
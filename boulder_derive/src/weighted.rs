@@ -0,0 +1,53 @@
+use proc_macro2 as pm2;
+
+pub(crate) struct WeightedItem {
+    value: syn::Expr,
+    weight: syn::Expr,
+}
+
+impl syn::parse::Parse for WeightedItem {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let value: syn::Expr = input.parse()?;
+        let _: syn::Token![=>] = input.parse()?;
+        let weight: syn::Expr = input.parse()?;
+        Ok(Self { value, weight })
+    }
+}
+
+pub(crate) struct WeightedCall {
+    items: Vec<WeightedItem>,
+}
+
+impl syn::parse::Parse for WeightedCall {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let punc =
+            syn::punctuated::Punctuated::<WeightedItem, syn::Token![,]>::parse_terminated(input)?;
+        Ok(Self {
+            items: punc.into_iter().collect(),
+        })
+    }
+}
+
+pub(crate) fn weighted_macro(call: WeightedCall) -> pm2::TokenStream {
+    let mut items = pm2::TokenStream::new();
+
+    for WeightedItem { value, weight } in call.items {
+        items.extend(quote::quote! {
+            {
+                let weight: u64 = #weight;
+                items.push((#value.into(), weight));
+            }
+        });
+    }
+
+    let res: pm2::TokenStream = quote::quote! {
+        {
+            let mut items = Vec::new();
+            #items
+
+            ::boulder::Weighted::new(items)
+        }
+    };
+
+    res
+}
@@ -71,6 +71,24 @@ fn test_generic() {
     assert_eq!(w.v.b, 7i32);
 }
 
+/// A wrapper from outside boulder, which builders and generators
+/// should pass through once it implements `BoulderWrapper`.
+#[derive(Debug)]
+pub struct Tracked<T> {
+    value: T,
+    revision: u32,
+}
+
+impl<T> boulder::BoulderWrapper for Tracked<T> {
+    type Inner = T;
+    fn wrap(inner: T) -> Self {
+        Tracked {
+            value: inner,
+            revision: 1,
+        }
+    }
+}
+
 mod builder_wrappers {
     use super::*;
 
@@ -161,6 +179,112 @@ mod builder_wrappers {
         assert_eq!(w.lock().unwrap().a, "hello".to_string());
         assert_eq!(w.lock().unwrap().b, 4i32);
     }
+
+    #[test]
+    fn test_box() {
+        let w = Box::<Womble>::builder().a("hello").b(4i16).build();
+        assert_eq!(std::any::TypeId::of::<Box<Womble>>(), w.type_id());
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 4i32);
+    }
+
+    #[test]
+    fn test_pin_box() {
+        let w = std::pin::Pin::<Box<Womble>>::builder()
+            .a("hello")
+            .b(4i16)
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<std::pin::Pin<Box<Womble>>>(),
+            w.type_id()
+        );
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 4i32);
+    }
+
+    #[test]
+    fn test_rw_lock() {
+        let w = std::sync::RwLock::<Womble>::builder()
+            .a("hello")
+            .b(4i16)
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::RwLock<Womble>>(),
+            w.type_id()
+        );
+        assert_eq!(w.read().unwrap().a, "hello".to_string());
+        assert_eq!(w.read().unwrap().b, 4i32);
+    }
+
+    #[test]
+    fn test_once_cell() {
+        let w = std::cell::OnceCell::<Womble>::builder()
+            .a("hello")
+            .b(4i16)
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<std::cell::OnceCell<Womble>>(),
+            w.type_id()
+        );
+        assert_eq!(w.get().unwrap().a, "hello".to_string());
+        assert_eq!(w.get().unwrap().b, 4i32);
+    }
+
+    // Cow needs a Clone type.
+    #[derive(Clone, Debug, Buildable)]
+    pub struct CowWomble {
+        #[boulder(default = "hullo")]
+        a: String,
+        #[boulder(default=super::foo(1))]
+        b: i32,
+    }
+
+    #[test]
+    fn test_cow() {
+        let w = std::borrow::Cow::<'static, CowWomble>::builder()
+            .a("hello")
+            .b(4i16)
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<std::borrow::Cow<'static, CowWomble>>(),
+            w.type_id()
+        );
+        assert!(matches!(w, std::borrow::Cow::Owned(_)));
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 4i32);
+    }
+
+    #[test]
+    fn test_arc_rw_lock() {
+        let w = std::sync::Arc::<std::sync::RwLock<Womble>>::builder()
+            .a("hello")
+            .b(4i16)
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::Arc<std::sync::RwLock<Womble>>>(),
+            w.type_id()
+        );
+        assert_eq!(w.read().unwrap().a, "hello".to_string());
+        assert_eq!(w.read().unwrap().b, 4i32);
+    }
+
+    #[test]
+    fn test_custom_wrapper() {
+        let w = Tracked::<Womble>::builder().a("hello").b(4i16).build();
+        assert_eq!(std::any::TypeId::of::<Tracked<Womble>>(), w.type_id());
+        assert_eq!(w.value.a, "hello".to_string());
+        assert_eq!(w.value.b, 4i32);
+        assert_eq!(w.revision, 1);
+
+        let w = Option::<Tracked<std::rc::Rc<Womble>>>::builder()
+            .a("hello")
+            .build();
+        assert_eq!(
+            std::any::TypeId::of::<Option<Tracked<std::rc::Rc<Womble>>>>(),
+            w.type_id()
+        );
+        assert_eq!(w.as_ref().map(|w| w.value.a.as_str()), Some("hello"));
+    }
 }
 
 mod builder_coverage {
@@ -614,6 +738,163 @@ mod generator_wrappers {
         assert_eq!(w2.borrow().a, "hello".to_string());
         assert_eq!(w2.borrow().b, 6);
     }
+
+    #[test]
+    fn test_box_generator() {
+        let mut g = Box::<Wizard>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(std::any::TypeId::of::<Box<Wizard>>(), w.type_id());
+        assert_eq!(std::any::TypeId::of::<Box<Wizard>>(), w2.type_id());
+
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 5);
+        assert_eq!(w2.a, "hello".to_string());
+        assert_eq!(w2.b, 6);
+    }
+
+    #[test]
+    fn test_pin_box_generator() {
+        let mut g = std::pin::Pin::<Box<Wizard>>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(
+            std::any::TypeId::of::<std::pin::Pin<Box<Wizard>>>(),
+            w.type_id()
+        );
+        assert_eq!(
+            std::any::TypeId::of::<std::pin::Pin<Box<Wizard>>>(),
+            w2.type_id()
+        );
+
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 5);
+        assert_eq!(w2.a, "hello".to_string());
+        assert_eq!(w2.b, 6);
+    }
+
+    #[test]
+    fn test_rw_lock_generator() {
+        let mut g = std::sync::RwLock::<Wizard>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::RwLock<Wizard>>(),
+            w.type_id()
+        );
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::RwLock<Wizard>>(),
+            w2.type_id()
+        );
+
+        assert_eq!(w.read().unwrap().a, "hello".to_string());
+        assert_eq!(w.read().unwrap().b, 5);
+        assert_eq!(w2.read().unwrap().a, "hello".to_string());
+        assert_eq!(w2.read().unwrap().b, 6);
+    }
+
+    #[test]
+    fn test_once_cell_generator() {
+        let mut g = std::cell::OnceCell::<Wizard>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(
+            std::any::TypeId::of::<std::cell::OnceCell<Wizard>>(),
+            w.type_id()
+        );
+        assert_eq!(
+            std::any::TypeId::of::<std::cell::OnceCell<Wizard>>(),
+            w2.type_id()
+        );
+
+        assert_eq!(w.get().unwrap().a, "hello".to_string());
+        assert_eq!(w.get().unwrap().b, 5);
+        assert_eq!(w2.get().unwrap().a, "hello".to_string());
+        assert_eq!(w2.get().unwrap().b, 6);
+    }
+
+    // Cow needs a Clone type.
+    #[derive(Clone, Debug, Generatable)]
+    pub struct CowWizard {
+        #[boulder(default = "hello")]
+        a: String,
+        #[boulder(generator=boulder::Inc(5))]
+        b: i32,
+    }
+
+    #[test]
+    fn test_cow_generator() {
+        let mut g = std::borrow::Cow::<'static, CowWizard>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(
+            std::any::TypeId::of::<std::borrow::Cow<'static, CowWizard>>(),
+            w.type_id()
+        );
+        assert_eq!(
+            std::any::TypeId::of::<std::borrow::Cow<'static, CowWizard>>(),
+            w2.type_id()
+        );
+
+        assert_eq!(w.a, "hello".to_string());
+        assert_eq!(w.b, 5);
+        assert_eq!(w2.a, "hello".to_string());
+        assert_eq!(w2.b, 6);
+    }
+
+    #[test]
+    fn test_arc_rw_lock_generator() {
+        let mut g = std::sync::Arc::<std::sync::RwLock<Wizard>>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::Arc<std::sync::RwLock<Wizard>>>(),
+            w.type_id()
+        );
+        assert_eq!(
+            std::any::TypeId::of::<std::sync::Arc<std::sync::RwLock<Wizard>>>(),
+            w2.type_id()
+        );
+
+        assert_eq!(w.read().unwrap().a, "hello".to_string());
+        assert_eq!(w.read().unwrap().b, 5);
+        assert_eq!(w2.read().unwrap().a, "hello".to_string());
+        assert_eq!(w2.read().unwrap().b, 6);
+    }
+
+    #[test]
+    fn test_custom_wrapper_generator() {
+        let mut g = Tracked::<Wizard>::generator();
+
+        let w = g.generate();
+        let w2 = g.generate();
+
+        assert_eq!(std::any::TypeId::of::<Tracked<Wizard>>(), w.type_id());
+        assert_eq!(w.value.a, "hello".to_string());
+        assert_eq!(w.value.b, 5);
+        assert_eq!(w.revision, 1);
+        assert_eq!(w2.value.b, 6);
+
+        let mut g = Tracked::<Option<Wizard>>::generator();
+        let w = g.generate();
+        assert_eq!(
+            std::any::TypeId::of::<Tracked<Option<Wizard>>>(),
+            w.type_id()
+        );
+        assert_eq!(w.value.map(|w| w.b), Some(5));
+    }
 }
 
 #[test]
@@ -1453,3 +1734,1500 @@ mod generator_coverage_generics {
         assert_eq!(m1.p5[0].c5, 55);
     }
 }
+
+#[derive(Debug, Generatable)]
+pub struct Otter {
+    #[boulder(generator=boulder::WithEdgeStrings::new(boulder::Pattern!("otter-{}", boulder::Inc(1)), 4))]
+    name: String,
+}
+
+#[test]
+fn test_edge_strings() {
+    let g = Otter::generator();
+    let names: Vec<_> = g.into_iter().take(8).map(|d| d.name).collect();
+    assert_eq!(
+        names,
+        vec![
+            "otter-1".to_string(),
+            "otter-2".to_string(),
+            "otter-3".to_string(),
+            "".to_string(),
+            "otter-4".to_string(),
+            "otter-5".to_string(),
+            "otter-6".to_string(),
+            " ".to_string(),
+        ]
+    );
+
+    let values = boulder::EdgeStrings::values();
+    let mut g = boulder::EdgeStrings::new();
+    for value in values.iter().chain(values.iter()) {
+        assert_eq!(&g.generate(), value);
+    }
+    assert!(values.iter().any(|v| v.contains('\0')));
+    assert!(values.iter().any(|v| v.chars().count() > 10000));
+}
+
+#[derive(Debug, Generatable)]
+pub struct Heron {
+    #[boulder(generator=boulder::Unique::new(boulder::Repeat!(1, 2, 2, 3, 1, 4, 5)))]
+    id: i32,
+    #[boulder(generator=boulder::HashSetOf::new(boulder::Repeat!("a", "a", "b", "c"), boulder::Repeat!(0usize, 1usize, 2usize, 3usize)))]
+    tags: std::collections::HashSet<String>,
+}
+
+#[test]
+fn test_unique() {
+    let mut g = Heron::generator();
+    let ids: Vec<_> = (&mut g).into_iter().take(3).map(|w| w.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    let w = g.generate();
+    assert_eq!(w.id, 4);
+    assert_eq!(w.tags.len(), 3);
+
+    let mut g = Heron::generator();
+    for i in 0..4 {
+        assert_eq!(g.generate().tags.len(), i);
+    }
+}
+
+#[test]
+#[should_panic(expected = "failed to find a new value of type i32")]
+fn test_unique_exhausted() {
+    let mut g = Heron::generator();
+    for _ in 0..6 {
+        g.generate();
+    }
+}
+
+#[derive(Debug, Generatable)]
+pub struct Octopus {
+    #[boulder(generator=boulder::Weighted!("active" => 90, "suspended" => 9, "deleted" => 1))]
+    status: String,
+    #[boulder(generator=boulder::Weighted!(1 => 1, 2 => 1).seeded(3))]
+    shard: i32,
+}
+
+#[test]
+fn test_weighted() {
+    let g = Octopus::generator();
+    let octopuses: Vec<_> = g.into_iter().take(300).collect();
+    for window in octopuses.chunks(100) {
+        let count = |s: &str| window.iter().filter(|k| k.status == s).count();
+        assert_eq!(count("active"), 90);
+        assert_eq!(count("suspended"), 9);
+        assert_eq!(count("deleted"), 1);
+    }
+    // The rare values are spread out, not bunched at one end
+    let suspended: Vec<_> = octopuses[..100]
+        .iter()
+        .enumerate()
+        .filter(|(_, k)| k.status == "suspended")
+        .map(|(i, _)| i)
+        .collect();
+    for pair in suspended.windows(2) {
+        assert!(pair[1] - pair[0] >= 5);
+        assert!(pair[1] - pair[0] <= 20);
+    }
+
+    let shards: Vec<_> = Octopus::generator()
+        .into_iter()
+        .take(100)
+        .map(|k| k.shard)
+        .collect();
+    let again: Vec<_> = Octopus::generator()
+        .into_iter()
+        .take(100)
+        .map(|k| k.shard)
+        .collect();
+    assert_eq!(shards, again);
+    assert!(shards.contains(&1));
+    assert!(shards.contains(&2));
+    assert_ne!(shards[..10], [1, 2, 1, 2, 1, 2, 1, 2, 1, 2]);
+}
+
+#[derive(Debug, Generatable)]
+pub struct Lizard {
+    #[boulder(generator=boulder::Maybe::new(boulder::Pattern!("nick-{}", boulder::Inc(1)), boulder::Frequency::Every(2)))]
+    nickname: Option<String>,
+    #[boulder(generator=boulder::Maybe::new(boulder::Inc(1), boulder::Frequency::Seeded { p: 0.5, seed: 11 }))]
+    age: Option<i32>,
+    #[boulder(generator=boulder::OkOrErr::new(boulder::Inc(0u8), boulder::Const("bad".to_string()), boulder::Frequency::Ratio(1, 4)))]
+    status: Result<u8, String>,
+}
+
+#[test]
+fn test_maybe() {
+    let g = Lizard::generator();
+    let b: Vec<_> = g.into_iter().take(8).collect();
+    assert_eq!(b[0].nickname, Some("nick-1".to_string()));
+    assert_eq!(b[1].nickname, None);
+    assert_eq!(b[2].nickname, Some("nick-2".to_string()));
+    assert_eq!(b[3].nickname, None);
+    assert_eq!(b[0].status, Ok(0));
+    assert_eq!(b[1].status, Ok(1));
+    assert_eq!(b[2].status, Ok(2));
+    assert_eq!(b[3].status, Err("bad".to_string()));
+    assert_eq!(b[4].status, Ok(3));
+    assert_eq!(b[7].status, Err("bad".to_string()));
+
+    let ages: Vec<_> = Lizard::generator()
+        .into_iter()
+        .take(100)
+        .map(|b| b.age)
+        .collect();
+    let missing = ages.iter().filter(|a| a.is_none()).count();
+    assert!(missing > 25 && missing < 75);
+    let again: Vec<_> = Lizard::generator()
+        .into_iter()
+        .take(100)
+        .map(|b| b.age)
+        .collect();
+    assert_eq!(ages, again);
+}
+
+#[derive(Debug, Generatable)]
+pub struct Goat {
+    #[boulder(generator=boulder::Sequence::named("goat_id"))]
+    id: u32,
+    heads: i32,
+}
+
+#[derive(Debug, Generatable)]
+pub struct Newt {
+    #[boulder(generator=boulder::Sequence::named("goat_id"))]
+    id: u64,
+    heads: i32,
+}
+
+#[test]
+fn test_shared() {
+    let heads = boulder::Shared::new(boulder::Inc(1));
+    let mut c = Goat::generator().heads(heads.clone());
+    let mut h = Newt::generator().heads(heads);
+
+    boulder::reset_sequence("goat_id");
+    let c1 = c.generate();
+    let h1 = h.generate();
+    let c2 = c.generate();
+    assert_eq!((c1.id, c1.heads), (1, 1));
+    assert_eq!((h1.id, h1.heads), (2, 2));
+    assert_eq!((c2.id, c2.heads), (3, 3));
+
+    boulder::reset_sequence("goat_id");
+    let h2 = h.generate();
+    assert_eq!((h2.id, h2.heads), (1, 4));
+
+    boulder::set_sequence("goat_id", 10);
+    assert_eq!(Goat::generator().generate().id, 10);
+    boulder::reset_sequences();
+    assert_eq!(Newt::generator().generate().id, 1);
+
+    boulder::set_sequence("goat_id", u64::MAX);
+    let mut ids = boulder::Sequence::<u64>::named("goat_id");
+    assert_eq!(ids.generate(), u64::MAX);
+    assert_eq!(ids.generate(), 0);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Phase {
+    Draft,
+    Review,
+    Published,
+    Archived,
+}
+
+fn phases() -> boulder::StateMachine<Phase> {
+    boulder::StateMachine::new(Phase::Draft)
+        .transition(Phase::Draft, Phase::Review)
+        .weighted_transition(Phase::Review, Phase::Draft, 1)
+        .weighted_transition(Phase::Review, Phase::Published, 2)
+        .transition(Phase::Published, Phase::Archived)
+}
+
+#[derive(Debug, Generatable)]
+pub struct Lion {
+    #[boulder(generator=phases().paths(boulder::Const(10usize)))]
+    history: Vec<Phase>,
+    #[boulder(sequence_generator=boulder::Const(3usize), generator=phases().seeded(3))]
+    recent: Vec<Phase>,
+}
+
+fn legal(states: &[Phase]) -> bool {
+    states.windows(2).all(|w| {
+        matches!(
+            (&w[0], &w[1]),
+            (Phase::Draft, Phase::Review)
+                | (Phase::Review, Phase::Draft)
+                | (Phase::Review, Phase::Published)
+                | (Phase::Published, Phase::Archived)
+        )
+    })
+}
+
+#[test]
+fn test_state_machine() {
+    let m: Vec<_> = Lion::generator().into_iter().take(20).collect();
+    assert_eq!(
+        m[0].history,
+        vec![
+            Phase::Draft,
+            Phase::Review,
+            Phase::Published,
+            Phase::Archived
+        ]
+    );
+    assert_eq!(
+        m[1].history,
+        vec![
+            Phase::Draft,
+            Phase::Review,
+            Phase::Draft,
+            Phase::Review,
+            Phase::Published,
+            Phase::Archived
+        ]
+    );
+    for item in &m {
+        assert_eq!(item.history[0], Phase::Draft);
+        assert_eq!(item.history.last(), Some(&Phase::Archived));
+        assert!(legal(&item.history));
+        assert_eq!(item.recent.len(), 3);
+        assert_eq!(item.recent[0], Phase::Draft);
+        assert!(legal(&item.recent));
+    }
+    let all: Vec<_> = m.iter().flat_map(|item| item.recent.clone()).collect();
+
+    let again: Vec<_> = Lion::generator()
+        .into_iter()
+        .take(20)
+        .flat_map(|item| item.recent)
+        .collect();
+    assert_eq!(all, again);
+}
+
+fn series_start() -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::parse_from_rfc3339("2022-05-18T00:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc)
+}
+
+#[derive(Debug, Generatable)]
+pub struct Whale {
+    #[boulder(generator=boulder::Sample::new(
+        boulder::Zip::new(
+            boulder::Time::new(series_start(), chrono::Duration::minutes(1)),
+            boulder::WithEvents::new(boulder::RandomWalk::new(50.0, 1.0, 9).bounds(40.0, 60.0))
+                .step_at(100, 1000.0)
+                .spike_at(150, -2000.0),
+        ),
+        boulder::Const(200usize),
+    ))]
+    depth: Vec<(chrono::DateTime<chrono::Utc>, f64)>,
+    #[boulder(generator=boulder::Sample::new(
+        boulder::WithEvents::new(boulder::Sine::new(0.0, 10.0, 24.0))
+            .spike_at(6, 5.0),
+        boulder::Const(48usize),
+    ))]
+    tide: Vec<f64>,
+}
+
+#[test]
+fn test_time_series() {
+    let l = Whale::generator().generate();
+    assert_eq!(l.depth.len(), 200);
+    assert_eq!(l.depth[0], (series_start(), 50.0));
+    assert_eq!(
+        l.depth[199].0,
+        series_start() + chrono::Duration::minutes(199)
+    );
+    for (i, (_, depth)) in l.depth.iter().enumerate() {
+        match i {
+            0..=99 => assert!((40.0..=60.0).contains(depth)),
+            150 => assert!((-1060.0..=-940.0).contains(depth)),
+            _ => assert!((1040.0..=1060.0).contains(depth)),
+        }
+    }
+    assert!((l.tide[6] - 15.0).abs() < 1e-9);
+    assert!((l.tide[30] - 10.0).abs() < 1e-9);
+    assert!((l.tide[18] + 10.0).abs() < 1e-9);
+
+    let again = Whale::generator().generate();
+    assert_eq!(l.depth, again.depth);
+}
+
+#[derive(Debug, Buildable, Generatable)]
+pub struct Oak {
+    #[boulder(generator=boulder::recursion_depth)]
+    depth: usize,
+    // Recursive fields hold their children boxed.
+    #[allow(clippy::vec_box)]
+    #[boulder(recursive(max_depth=4, branching=boulder::Repeat!(0usize, 2usize, 3usize)))]
+    children: Vec<Box<Oak>>,
+    #[boulder(buildable)]
+    seed: Option<Box<Acorn>>,
+}
+
+#[derive(Debug, Buildable, Generatable)]
+pub struct Acorn {
+    #[boulder(default = 3)]
+    size: i32,
+}
+
+fn oak_height(t: &Oak) -> usize {
+    t.children
+        .iter()
+        .map(|c| 1 + oak_height(c))
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_recursive() {
+    let mut g = Oak::generator();
+    let t = g.generate();
+    assert_eq!(t.depth, 0);
+    assert!(t.children.is_empty());
+    assert_eq!(t.seed.as_ref().unwrap().size, 3);
+
+    let t = g.generate();
+    assert_eq!(t.children.len(), 2);
+    for child in &t.children {
+        assert_eq!(child.depth, 1);
+    }
+
+    for t in g.into_iter().take(20) {
+        assert_eq!(t.depth, 0);
+        assert!(oak_height(&t) <= 4);
+    }
+    assert_eq!(boulder::recursion_depth(), 0);
+
+    let b = <Box<Oak>>::builder().depth(7usize).build();
+    assert_eq!(b.depth, 7);
+    assert!(b.children.is_empty());
+    let g = <Box<Acorn>>::generator().generate();
+    assert_eq!(g.size, 3);
+}
+
+/// A type with a hand-written builder and generator, which should
+/// compose with derived types exactly as if it were derived.
+#[derive(Debug)]
+pub struct Magpie {
+    weight: u32,
+}
+
+pub struct MagpieBuilder<T> {
+    weight: u32,
+    _result: std::marker::PhantomData<T>,
+}
+
+impl<T> MagpieBuilder<T> {
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+impl Builder for MagpieBuilder<Magpie> {
+    type Result = Magpie;
+    fn build(self) -> Magpie {
+        Magpie {
+            weight: self.weight,
+        }
+    }
+}
+
+impl<W> Builder for MagpieBuilder<W>
+where
+    W: boulder::BoulderWrapper,
+    MagpieBuilder<W::Inner>: Builder<Result = W::Inner>,
+{
+    type Result = W;
+    fn build(self) -> W {
+        let inner = MagpieBuilder::<W::Inner> {
+            weight: self.weight,
+            _result: Default::default(),
+        };
+        W::wrap(inner.build())
+    }
+}
+
+impl boulder::BuildableBase for Magpie {
+    type Builder<T> = MagpieBuilder<T>;
+    fn builder_for<T>() -> MagpieBuilder<T> {
+        MagpieBuilder {
+            weight: 500,
+            _result: Default::default(),
+        }
+    }
+}
+
+pub struct MagpieGenerator<T> {
+    next: u32,
+    _result: std::marker::PhantomData<T>,
+}
+
+impl Generator for MagpieGenerator<Magpie> {
+    type Output = Magpie;
+    fn generate(&mut self) -> Magpie {
+        self.next += 100;
+        Magpie { weight: self.next }
+    }
+}
+
+impl<W> Generator for MagpieGenerator<W>
+where
+    W: boulder::BoulderWrapper + 'static,
+    MagpieGenerator<W::Inner>: Generator<Output = W::Inner>,
+{
+    type Output = W;
+    fn generate(&mut self) -> W {
+        let mut inner = MagpieGenerator::<W::Inner> {
+            next: self.next,
+            _result: Default::default(),
+        };
+        let value = inner.generate();
+        self.next = inner.next;
+        W::wrap(value)
+    }
+}
+
+impl boulder::GeneratableBase for Magpie {
+    type Generator<T> = MagpieGenerator<T>;
+    fn generator_for<T>() -> MagpieGenerator<T> {
+        MagpieGenerator {
+            next: 0,
+            _result: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Buildable, Generatable)]
+pub struct Nest {
+    #[boulder(buildable(weight = 900u32), generatable)]
+    roof: Option<Magpie>,
+    #[boulder(buildable, generatable, sequence = 2usize)]
+    walls: Vec<std::rc::Rc<Magpie>>,
+}
+
+#[test]
+fn test_hand_written_base() {
+    let g = Magpie::builder().weight(7u32).build();
+    assert_eq!(g.weight, 7);
+    let g = std::sync::Arc::<std::sync::Mutex<Magpie>>::builder().build();
+    assert_eq!(g.lock().unwrap().weight, 500);
+
+    let c = Nest::builder().build();
+    assert_eq!(c.roof.map(|g| g.weight), Some(900));
+    assert_eq!(
+        c.walls.iter().map(|g| g.weight).collect::<Vec<_>>(),
+        vec![100, 200]
+    );
+
+    let mut gen = Nest::generator();
+    let c = gen.generate();
+    assert_eq!(c.roof.map(|g| g.weight), Some(100));
+    assert_eq!(
+        c.walls.iter().map(|g| g.weight).collect::<Vec<_>>(),
+        vec![100, 200]
+    );
+
+    let mut gen = Box::<Magpie>::generator();
+    assert_eq!(gen.generate().weight, 100);
+    assert_eq!(gen.generate().weight, 200);
+}
+
+mod observatory {
+    pub struct Telescope {
+        pub aperture: u32,
+        pub name: String,
+    }
+
+    pub struct Lens {
+        focal_length: u32,
+    }
+
+    impl Lens {
+        pub fn new(focal_length: u32) -> Self {
+            Lens { focal_length }
+        }
+
+        pub fn focal_length(&self) -> u32 {
+            self.focal_length
+        }
+    }
+}
+
+#[derive(Buildable, Generatable)]
+#[boulder(remote = "observatory::Telescope")]
+pub struct TelescopeDef {
+    #[boulder(default = 200u32, generator = boulder::Inc(100u32))]
+    aperture: u32,
+    #[boulder(default = "Hale", generator = boulder::Pattern!("scope-{}", boulder::Inc(1)))]
+    name: String,
+}
+
+#[derive(Buildable, Generatable)]
+#[boulder(remote = observatory::Lens, constructor = observatory::Lens::new)]
+pub struct LensDef {
+    #[boulder(default = 50u32, generator = boulder::Inc(10u32))]
+    focal_length: u32,
+}
+
+#[derive(Buildable, Generatable)]
+pub struct Observatory {
+    #[boulder(
+        default = TelescopeDef::builder().name("Yerkes").build(),
+        generator = TelescopeDef::generator()
+    )]
+    telescope: observatory::Telescope,
+    #[boulder(default = LensDef::builder().build(), generator = LensDef::generator())]
+    lens: observatory::Lens,
+}
+
+#[test]
+fn test_remote() {
+    let t = TelescopeDef::builder().aperture(10u32).build();
+    assert_eq!(t.aperture, 10);
+    assert_eq!(t.name, "Hale");
+
+    let l = LensDef::builder().focal_length(35u32).build();
+    assert_eq!(l.focal_length(), 35);
+
+    let o = Observatory::builder().build();
+    assert_eq!(o.telescope.aperture, 200);
+    assert_eq!(o.telescope.name, "Yerkes");
+    assert_eq!(o.lens.focal_length(), 50);
+
+    let mut gen = TelescopeDef::generator();
+    let t = gen.generate();
+    assert_eq!(t.aperture, 100);
+    assert_eq!(t.name, "scope-1");
+    let t = gen.generate();
+    assert_eq!(t.aperture, 101);
+    assert_eq!(t.name, "scope-2");
+
+    let mut gen = Observatory::generator();
+    let o = gen.generate();
+    assert_eq!(o.telescope.aperture, 100);
+    assert_eq!(o.lens.focal_length(), 10);
+    let o = gen.generate();
+    assert_eq!(o.telescope.name, "scope-2");
+    assert_eq!(o.lens.focal_length(), 11);
+}
+
+#[derive(Debug, Buildable, Generatable)]
+pub struct Almanac {
+    #[boulder(buildable, generatable)]
+    year: u32,
+    #[boulder(buildable, generatable)]
+    title: String,
+    #[boulder(buildable, generatable)]
+    tides: Vec<u16>,
+    #[boulder(buildable, generatable)]
+    moons: std::collections::HashMap<String, bool>,
+    #[boulder(buildable, generatable)]
+    eclipse: (u8, (char, [i64; 2])),
+    #[boulder(buildable, generatable)]
+    published: chrono::DateTime<chrono::Utc>,
+    #[boulder(generatable, sequence = 3usize)]
+    seasons: [Option<String>; 3],
+}
+
+#[test]
+fn test_builtin() {
+    let a = Almanac::builder().build();
+    assert_eq!(a.year, 0);
+    assert_eq!(a.title, "");
+    assert!(a.tides.is_empty());
+    assert!(a.moons.is_empty());
+    assert_eq!(a.eclipse, (0, ('\0', [0, 0])));
+    assert_eq!(a.published, chrono::DateTime::UNIX_EPOCH);
+    assert_eq!(
+        a.seasons,
+        [
+            Some("string-0".to_string()),
+            Some("string-1".to_string()),
+            Some("string-2".to_string())
+        ]
+    );
+
+    let mut gen = Almanac::generator();
+    let a = gen.generate();
+    assert_eq!(a.year, 0);
+    assert_eq!(a.title, "string-0");
+    assert_eq!(a.tides, vec![0]);
+    assert_eq!(a.moons.len(), 1);
+    assert_eq!(a.moons.get("string-0"), Some(&false));
+    assert_eq!(a.eclipse, (0, ('a', [0, 1])));
+    assert_eq!(a.published, chrono::DateTime::UNIX_EPOCH);
+    assert_eq!(
+        a.seasons,
+        [
+            Some("string-0".to_string()),
+            Some("string-1".to_string()),
+            Some("string-2".to_string())
+        ]
+    );
+    let a = gen.generate();
+    assert_eq!(a.year, 1);
+    assert_eq!(a.title, "string-1");
+    assert_eq!(a.tides, vec![1, 2]);
+    assert_eq!(a.moons.len(), 2);
+    assert_eq!(a.eclipse, (1, ('b', [2, 3])));
+    assert_eq!(
+        a.published,
+        chrono::DateTime::UNIX_EPOCH + chrono::Duration::days(1)
+    );
+    assert_eq!(a.seasons[0], Some("string-3".to_string()));
+
+    let mut gen = Option::<std::rc::Rc<u8>>::generator();
+    assert_eq!(gen.generate().as_deref(), Some(&0));
+    assert_eq!(gen.generate().as_deref(), Some(&1));
+    assert_eq!(Box::<i32>::builder().value(5).build(), Box::new(5));
+}
+
+#[derive(Debug, Buildable, Generatable)]
+#[boulder(auto)]
+pub struct Lighthouse {
+    keeper: String,
+    r#type: String,
+    beam: f32,
+    flashes: u16,
+    lit: bool,
+    fog: Option<Option<u32>>,
+    #[boulder(default = "granite")]
+    stone: String,
+    #[boulder(generatable)]
+    visitors: Vec<u8>,
+    bells: Vec<u32>,
+}
+
+#[test]
+fn test_auto() {
+    let l = Lighthouse::builder().build();
+    assert_eq!(l.keeper, "");
+    assert_eq!(l.flashes, 0);
+
+    let mut gen = Lighthouse::generator();
+    let l = gen.generate();
+    assert_eq!(l.keeper, "keeper-0");
+    assert_eq!(l.r#type, "type-0");
+    assert_eq!(l.beam, 0.0);
+    assert_eq!(l.flashes, 0);
+    assert!(!l.lit);
+    assert_eq!(l.fog, Some(Some(0)));
+    assert_eq!(l.stone, "granite");
+    assert_eq!(l.visitors, vec![0]);
+    assert!(l.bells.is_empty());
+    let l = gen.generate();
+    assert_eq!(l.keeper, "keeper-1");
+    assert_eq!(l.beam, 1.0);
+    assert_eq!(l.flashes, 1);
+    assert!(l.lit);
+    assert_eq!(l.fog, None);
+    assert_eq!(l.stone, "granite");
+    assert!(l.bells.is_empty());
+    let l = gen.generate();
+    assert!(!l.lit);
+    assert_eq!(l.fog, Some(None));
+    let l = gen.generate();
+    assert_eq!(l.fog, None);
+    let l = gen.generate();
+    assert_eq!(l.fog, Some(Some(1)));
+
+    let mut gen = Lighthouse::generator().keeper(|| "Bob".to_string());
+    assert_eq!(gen.generate().keeper, "Bob");
+}
+
+#[derive(Debug, Buildable, Generatable)]
+#[boulder(
+    preset(archmage, rank = "archmage", level = 99u32),
+    preset(banished, active = false)
+)]
+#[boulder(preset(unremarkable))]
+pub struct Sorceress3 {
+    #[boulder(default = "novice", generator = boulder::Pattern!("rank-{}", boulder::Inc(1)))]
+    rank: String,
+    #[boulder(default = 10u32, generator = boulder::Inc(10u32))]
+    level: u32,
+    #[boulder(default = true)]
+    active: bool,
+}
+
+#[test]
+fn test_presets() {
+    let w = Sorceress3::builder().archmage().build();
+    assert_eq!(w.rank, "archmage");
+    assert_eq!(w.level, 99);
+    assert!(w.active);
+
+    let w = Sorceress3::builder()
+        .archmage()
+        .banished()
+        .level(5u32)
+        .build();
+    assert_eq!(w.rank, "archmage");
+    assert_eq!(w.level, 5);
+    assert!(!w.active);
+
+    let w = Sorceress3::builder().rank("sage").archmage().build();
+    assert_eq!(w.rank, "archmage");
+
+    let w = Sorceress3::builder().unremarkable().build();
+    assert_eq!(w.rank, "novice");
+
+    let mut gen = Sorceress3::generator().archmage();
+    let w = gen.generate();
+    assert_eq!(w.rank, "archmage");
+    assert_eq!(w.level, 99);
+    let w = gen.generate();
+    assert_eq!(w.rank, "archmage");
+    assert_eq!(w.level, 99);
+
+    let mut gen = Sorceress3::generator()
+        .archmage()
+        .banished()
+        .level(boulder::Inc(1u32));
+    let w = gen.generate();
+    assert_eq!((w.level, w.active), (1, false));
+    let w = gen.generate();
+    assert_eq!((w.level, w.active), (2, false));
+}
+
+#[derive(Clone, Debug, Buildable, Generatable)]
+pub struct Moat {
+    #[boulder(default = 3u32, generator = boulder::Inc(3u32))]
+    depth: u32,
+    #[boulder(default = "murky")]
+    water: String,
+}
+
+#[derive(Debug, Buildable, Generatable)]
+pub struct Keep {
+    #[boulder(
+        buildable(depth = 10u32),
+        generatable(depth = boulder::Inc(20u32))
+    )]
+    moat: Moat,
+    #[boulder(default = 4u8)]
+    towers: u8,
+}
+
+#[test]
+fn test_field_with() {
+    let k = Keep::builder().moat_with(|b| b.water("clear")).build();
+    assert_eq!(k.moat.depth, 10);
+    assert_eq!(k.moat.water, "clear");
+
+    let k = Keep::builder()
+        .moat_with(|b| b.water("clear"))
+        .moat(Moat {
+            depth: 1,
+            water: "dry".to_string(),
+        })
+        .build();
+    assert_eq!(k.moat.depth, 1);
+    assert_eq!(k.moat.water, "dry");
+
+    let k = Keep::builder()
+        .moat(Moat {
+            depth: 1,
+            water: "dry".to_string(),
+        })
+        .moat_with(|b| b.depth(2u32))
+        .build();
+    assert_eq!(k.moat.depth, 2);
+    assert_eq!(k.moat.water, "murky");
+
+    let k = Option::<Keep>::builder()
+        .moat_with(|b| b.depth(5u32))
+        .build()
+        .unwrap();
+    assert_eq!(k.moat.depth, 5);
+
+    let mut gen = Keep::generator().moat_with(|g| g.water(|| "clear".to_string()));
+    let k = gen.generate();
+    assert_eq!(k.moat.depth, 20);
+    assert_eq!(k.moat.water, "clear");
+    let k = gen.generate();
+    assert_eq!(k.moat.depth, 21);
+    assert_eq!(k.towers, 4);
+}
+
+#[derive(Buildable)]
+pub struct Sluice {
+    #[boulder(default = 2u8)]
+    gates: u8,
+    #[boulder(default = std::sync::Mutex::new(0u8))]
+    flow: std::sync::Mutex<u8>,
+}
+
+#[test]
+fn test_to_builder() {
+    let k = Keep::builder().towers(7).build();
+    let k2 = k.to_builder().moat_with(|b| b.water("clear")).build();
+    assert_eq!(k2.towers, 7);
+    assert_eq!(k2.moat.depth, 10);
+    assert_eq!(k2.moat.water, "clear");
+    assert_eq!(k.moat.water, "murky");
+
+    let k3 = <Keep as Buildable>::Builder::from(k).towers(8).build();
+    assert_eq!(k3.towers, 8);
+    assert_eq!(k3.moat.water, "murky");
+
+    let k4 = <Option<Keep> as Buildable>::Builder::from(k3)
+        .build()
+        .unwrap();
+    assert_eq!(k4.towers, 8);
+
+    let s = Sluice::builder()
+        .gates(3)
+        .flow(std::sync::Mutex::new(4))
+        .build();
+    let s = <Sluice as Buildable>::Builder::from(s).gates(5).build();
+    assert_eq!(s.gates, 5);
+    assert_eq!(*s.flow.lock().unwrap(), 4);
+}
+
+#[derive(Debug, PartialEq, Buildable)]
+pub struct LineItem {
+    #[boulder(default = "widget")]
+    sku: String,
+    #[boulder(default = 2u32)]
+    qty: u32,
+}
+
+#[derive(Debug, Buildable)]
+pub struct Order {
+    #[boulder(buildable(qty = 1u32), sequence = 3usize)]
+    lines: Vec<LineItem>,
+    #[boulder(default = "urgent", sequence = 1usize)]
+    tags: Vec<String>,
+    #[boulder(buildable, sequence = 2usize)]
+    codes: [u32; 2],
+}
+
+#[test]
+fn test_sequence_setters() {
+    let o = Order::builder()
+        .push_lines(LineItem {
+            sku: "gadget".to_string(),
+            qty: 4,
+        })
+        .push_tags("fragile")
+        .extend_tags(["gift", "late"])
+        .build();
+    assert_eq!(o.lines.len(), 4);
+    assert_eq!(o.lines[0].qty, 1);
+    assert_eq!(o.lines[3].sku, "gadget");
+    assert_eq!(o.tags, vec!["urgent", "fragile", "gift", "late"]);
+
+    let o = Order::builder()
+        .lines_at(2, |b| b.qty(7u32))
+        .codes_at(1, |b| b.value(9u32))
+        .build();
+    assert_eq!(o.lines.len(), 3);
+    assert_eq!(o.lines[1].qty, 1);
+    assert_eq!(o.lines[2].qty, 7);
+    assert_eq!(o.lines[2].sku, "widget");
+    assert_eq!(o.codes, [0, 9]);
+
+    let o = Order::builder()
+        .lines_len(5)
+        .tags_len(0)
+        .lines_at(4, |b| b.sku("gizmo"))
+        .build();
+    assert_eq!(o.lines.len(), 5);
+    assert!(o.lines[..4].iter().all(|l| l.sku == "widget" && l.qty == 1));
+    assert_eq!(o.lines[4].sku, "gizmo");
+    assert!(o.tags.is_empty());
+
+    let o = Order::builder()
+        .extend_lines(vec![LineItem::builder().build()])
+        .build();
+    assert_eq!(o.lines.len(), 4);
+    assert_eq!(o.lines[3].qty, 2);
+}
+
+#[test]
+#[should_panic(expected = "index 3 is out of range for `lines`")]
+fn test_sequence_at_out_of_range() {
+    Order::builder().lines_at(3, |b| b.qty(7u32)).build();
+}
+
+#[derive(Debug, Buildable, Generatable)]
+#[boulder(setter(strip_option), preset(anonymous, nickname = "anon", bio = "?"))]
+pub struct Profile {
+    /// The name shown to other users.
+    nickname: Option<String>,
+    #[boulder(default = Some(3u8), setter(strip_option = false))]
+    level: Option<u8>,
+    #[boulder(default = 1.5, setter(into = false))]
+    score: f64,
+    #[boulder(setter(prefix = "with_"))]
+    bio: String,
+    #[boulder(setter(into))]
+    r#type: Option<String>,
+    #[cfg(any())]
+    hidden: String,
+    #[cfg(test)]
+    shown: u8,
+}
+
+#[test]
+fn test_setter_options() {
+    let p = Profile::builder()
+        .nickname("ace")
+        .level(Some(4u8))
+        .score(2.0)
+        .with_bio("hello")
+        .r#type("admin")
+        .shown(1u8)
+        .build();
+    assert_eq!(p.nickname.as_deref(), Some("ace"));
+    assert_eq!(p.level, Some(4));
+    assert_eq!(p.score, 2.0);
+    assert_eq!(p.bio, "hello");
+    assert_eq!(p.r#type.as_deref(), Some("admin"));
+    assert_eq!(p.shown, 1);
+
+    let p = Profile::builder()
+        .nickname("ace")
+        .unset_nickname()
+        .unset_level()
+        .build();
+    assert_eq!(p.nickname, None);
+    assert_eq!(p.level, None);
+    assert_eq!(p.score, 1.5);
+
+    let p = Profile::builder().anonymous().build();
+    assert_eq!(p.nickname.as_deref(), Some("anon"));
+    assert_eq!(p.bio, "?");
+
+    let p = p.to_builder().unset_type().build();
+    assert_eq!(p.bio, "?");
+    assert_eq!(p.r#type, None);
+
+    let p = Profile::generator()
+        .nickname(|| Some("gen".to_string()))
+        .generate();
+    assert_eq!(p.nickname.as_deref(), Some("gen"));
+}
+
+#[derive(Debug, Buildable, Generatable)]
+#[boulder(transient(premium: bool, users: usize = 2))]
+pub struct Subscription {
+    #[boulder(default_from = |t| if t.premium { 100 } else { 10 })]
+    quota: u32,
+    #[boulder(default_from = |t| vec![0; t.users])]
+    seats: Vec<u8>,
+    #[boulder(default = "basic")]
+    plan: String,
+}
+
+#[test]
+fn test_transients() {
+    let s = Subscription::builder().build();
+    assert_eq!(s.quota, 10);
+    assert_eq!(s.seats, vec![0, 0]);
+    assert_eq!(s.plan, "basic");
+
+    let s = Subscription::builder().premium(true).users(3usize).build();
+    assert_eq!(s.quota, 100);
+    assert_eq!(s.seats, vec![0, 0, 0]);
+
+    let s = Subscription::builder().premium(true).quota(5u32).build();
+    assert_eq!(s.quota, 5);
+
+    let s = s.to_builder().premium(false).build();
+    assert_eq!(s.quota, 5);
+    assert_eq!(s.seats, vec![0, 0]);
+
+    let mut g = Subscription::generator().premium(true);
+    assert_eq!(g.generate().quota, 100);
+    let mut g = g.users(1usize).quota(boulder::Inc(1u32));
+    let s = g.generate();
+    assert_eq!(s.quota, 1);
+    assert_eq!(s.seats, vec![0]);
+    assert_eq!(g.generate().quota, 2);
+}
+
+fn normalise_ticket(ticket: &mut Ticket) {
+    ticket.title = ticket.title.trim().to_string();
+}
+
+#[derive(Debug, Buildable, Generatable)]
+#[boulder(after_build = normalise_ticket)]
+pub struct Ticket {
+    #[boulder(default = "  untitled ", generator = boulder::Pattern!(" ticket-{} ", boulder::Inc(0)))]
+    title: String,
+    #[boulder(generator = boulder::Inc(1u32))]
+    id: u32,
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_after_build() {
+    let t = Ticket::builder().build();
+    assert_eq!(t.title, "untitled");
+
+    let t = Ticket::builder()
+        .after_build(|t| t.tags.push(t.title.clone()))
+        .after_build(|t| t.id = t.tags.len() as u32 * 10)
+        .build();
+    assert_eq!(t.tags, vec!["untitled".to_string()]);
+    assert_eq!(t.id, 10);
+
+    let t = Box::<Ticket>::builder().after_build(|t| t.id = 7).build();
+    assert_eq!(t.id, 7);
+
+    // Builders with hooks can still be sent to other threads.
+    let builder = Ticket::builder().after_build(|t| t.id = 3);
+    let t = std::thread::spawn(move || builder.build()).join().unwrap();
+    assert_eq!(t.id, 3);
+
+    let mut seen = Vec::new();
+    let mut g = Ticket::generator().after_build(move |t| {
+        seen.push(t.id);
+        t.tags = seen.iter().map(|id| id.to_string()).collect();
+    });
+    let t = g.generate();
+    assert_eq!(t.title, "ticket-0");
+    assert_eq!(t.tags, vec!["1".to_string()]);
+    let t = g.generate();
+    assert_eq!(t.tags, vec!["1".to_string(), "2".to_string()]);
+}
+
+#[derive(Debug, Clone, Buildable, Generatable)]
+pub struct Address {
+    #[boulder(default = "1 High Street", generator = boulder::Pattern!("{} High Street", boulder::Inc(1)))]
+    street: String,
+}
+
+#[derive(Debug, Clone, Buildable, Generatable)]
+pub struct Customer {
+    #[boulder(default = "Ada")]
+    name: String,
+    #[boulder(buildable, generatable)]
+    address: Address,
+}
+
+#[derive(Debug, Clone, Buildable, Generatable)]
+pub struct Purchase {
+    #[boulder(buildable, generatable)]
+    customer: Customer,
+    #[boulder(buildable, sequence = 2usize)]
+    gifts: Vec<Address>,
+    #[boulder(generator = boulder::Inc(1u32))]
+    quantity: u32,
+}
+
+#[derive(Default)]
+struct Log(Vec<String>);
+
+impl boulder::Repository<Address> for Log {
+    type Output = ();
+    fn insert(&mut self, value: &Address) {
+        self.0.push(format!("address {}", value.street));
+    }
+}
+
+impl boulder::Repository<Customer> for Log {
+    type Output = ();
+    fn insert(&mut self, value: &Customer) {
+        self.0.push(format!("customer {}", value.name));
+    }
+}
+
+impl boulder::Repository<Purchase> for Log {
+    type Output = usize;
+    fn insert(&mut self, value: &Purchase) -> usize {
+        self.0.push(format!("purchase {}", value.quantity));
+        self.0.len()
+    }
+}
+
+#[test]
+fn test_create() {
+    let mut log = Log::default();
+    let n = Purchase::builder()
+        .quantity(4u32)
+        .customer_with(|c| c.name("Bob"))
+        .create(&mut log);
+    assert_eq!(n, 5);
+    assert_eq!(
+        log.0,
+        vec![
+            "address 1 High Street",
+            "customer Bob",
+            "address 1 High Street",
+            "address 1 High Street",
+            "purchase 4",
+        ]
+    );
+
+    let mut repo = boulder::MemoryRepository::new();
+    assert!(repo.is_empty());
+    let id = Customer::builder().name("Cy").create(&mut repo);
+    assert_eq!(id, 0);
+    let id = Purchase::builder().create(&mut repo);
+    assert_eq!(id, 0);
+    assert_eq!(repo.len::<Customer>(), 2);
+    assert_eq!(repo.len::<Address>(), 4);
+    assert_eq!(repo.get::<Customer>(0).unwrap().name, "Cy");
+    assert_eq!(repo.get::<Purchase>(id).unwrap().customer.name, "Ada");
+    assert!(repo.get::<Purchase>(1).is_none());
+
+    let mut repo = boulder::MemoryRepository::new();
+    let mut g = Purchase::generator();
+    assert_eq!(g.create(&mut repo), 0);
+    assert_eq!(g.create(&mut repo), 1);
+    let quantities: Vec<_> = repo.all::<Purchase>().map(|p| p.quantity).collect();
+    assert_eq!(quantities, vec![1, 2]);
+    let streets: Vec<_> = repo
+        .all::<Customer>()
+        .map(|c| c.address.street.clone())
+        .collect();
+    assert_eq!(streets, vec!["1 High Street", "2 High Street"]);
+    assert_eq!(repo.len::<Address>(), 6);
+}
+
+#[derive(Buildable)]
+pub struct Delivery {
+    #[boulder(buildable)]
+    customer: std::sync::Mutex<Customer>,
+    #[boulder(buildable(street = "2 High Street"))]
+    depot: std::cell::RefCell<Address>,
+    #[boulder(buildable)]
+    returns: std::pin::Pin<Box<Address>>,
+    #[boulder(buildable)]
+    billing: Option<std::sync::RwLock<Address>>,
+}
+
+impl boulder::Repository<Delivery> for Log {
+    type Output = ();
+    fn insert(&mut self, value: &Delivery) {
+        let name = value.customer.lock().unwrap().name.clone();
+        self.0.push(format!("delivery for {}", name));
+    }
+}
+
+#[test]
+fn test_create_wrappers() {
+    let mut log = Log::default();
+    Delivery::builder().create(&mut log);
+    assert_eq!(
+        log.0,
+        vec![
+            "address 1 High Street",
+            "customer Ada",
+            "address 2 High Street",
+            "address 1 High Street",
+            "address 1 High Street",
+            "delivery for Ada",
+        ]
+    );
+}
+
+#[derive(Debug, Clone, Buildable)]
+pub struct Server {
+    #[boulder(default = "localhost")]
+    host: String,
+    #[boulder(default = 8080u16)]
+    port: u16,
+    #[boulder(buildable)]
+    owner: Address,
+    #[boulder(default = Some("eu".to_string()))]
+    region: Option<String>,
+}
+
+#[derive(Debug, Clone, Buildable)]
+#[boulder(patch(derive(Clone, Debug)), transient(secure: bool))]
+pub struct Deployment {
+    #[boulder(default = "web")]
+    name: String,
+    #[boulder(buildable(port = 80u16))]
+    server: Server,
+    #[boulder(default_from = |t| if t.secure { 443 } else { 80 })]
+    port: u16,
+    #[boulder(default = 1usize)]
+    replicas: usize,
+}
+
+#[test]
+fn test_patch() {
+    let patch = DeploymentPatch {
+        name: Some("api".to_string()),
+        replicas: Some(3),
+        ..Default::default()
+    };
+    let d = Deployment::builder().apply(patch.clone()).build();
+    assert_eq!(d.name, "api");
+    assert_eq!(d.replicas, 3);
+    assert_eq!(d.port, 80);
+    assert_eq!(d.server.port, 80);
+
+    // Later setters win over a patch, and a patch over earlier
+    // setters.
+    let d = Deployment::builder()
+        .replicas(5usize)
+        .apply(patch.clone())
+        .name("worker")
+        .build();
+    assert_eq!((d.name.as_str(), d.replicas), ("worker", 3));
+
+    let d = Deployment::builder()
+        .server_with(|s| s.host("example.org"))
+        .apply(DeploymentPatch {
+            server: Some(Server::builder().port(1u16).build()),
+            port: Some(8443),
+            ..Default::default()
+        })
+        .secure(true)
+        .build();
+    assert_eq!(d.server.host, "localhost");
+    assert_eq!(d.server.port, 1);
+    assert_eq!(d.port, 8443);
+
+    let empty = DeploymentPatch::default();
+    assert!(empty.name.is_none() && empty.server.is_none());
+}
+
+#[test]
+fn test_merge() {
+    let base = || {
+        Deployment::builder()
+            .name("base")
+            .replicas(2usize)
+            .after_build(|d| d.name.push_str("-a"))
+    };
+
+    // Only what was set on the later builder replaces the earlier
+    // builder's values.
+    let d = base()
+        .merge(
+            Deployment::builder()
+                .replicas(7usize)
+                .after_build(|d| d.name.push_str("-b")),
+        )
+        .build();
+    assert_eq!(d.name, "base-a-b");
+    assert_eq!(d.replicas, 7);
+    assert_eq!(d.port, 80);
+
+    let d = base()
+        .merge(
+            Deployment::builder()
+                .secure(true)
+                .server_with(|s| s.port(9u16)),
+        )
+        .build();
+    assert_eq!(d.port, 443);
+    assert_eq!(d.server.port, 9);
+    assert_eq!(d.replicas, 2);
+
+    let d = Deployment::builder()
+        .secure(true)
+        .merge(Deployment::builder())
+        .build();
+    assert_eq!(d.port, 443);
+
+    // A builder made from a value sets every field.
+    let existing = Deployment::builder().name("old").port(1u16).build();
+    let d = base().merge(existing.to_builder()).build();
+    assert_eq!((d.name.as_str(), d.port, d.replicas), ("old-a", 1, 1));
+
+    let s = Server::builder()
+        .unset_region()
+        .merge(Server::builder().host("a"))
+        .build();
+    assert_eq!((s.host.as_str(), s.region), ("a", None));
+}
+
+#[derive(Debug, Clone, Buildable, Generatable)]
+#[boulder(transient(vip: bool))]
+pub struct Guest {
+    #[boulder(default = "guest", generator = boulder::Pattern!("guest-{}", boulder::Inc(1)))]
+    name: String,
+    #[boulder(default = 1u32, generator = boulder::Inc(10u32))]
+    table: u32,
+    #[boulder(default_from = |t| if t.vip { 2 } else { 1 })]
+    drinks: u32,
+    #[boulder(buildable, generatable)]
+    address: Address,
+}
+
+#[test]
+fn test_builder_templates() {
+    let template = Guest::builder().table(4u32).vip(true);
+    let copy = template.clone().name("Bo");
+    let a = template.build_ref();
+    let b = template.build_ref();
+    assert_eq!((a.name.as_str(), a.table, a.drinks), ("guest", 4, 2));
+    assert_eq!((b.name.as_str(), b.table), ("guest", 4));
+    assert_eq!(copy.build().name, "Bo");
+
+    let template = template.after_build(|g| g.table += 1);
+    let guests = template.build_many(3);
+    let names: Vec<_> = guests.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, vec!["guest-1", "guest-2", "guest-3"]);
+    assert!(guests.iter().all(|g| g.table == 5 && g.drinks == 2));
+    let streets: Vec<_> = guests.iter().map(|g| g.address.street.as_str()).collect();
+    assert_eq!(
+        streets,
+        vec!["1 High Street", "2 High Street", "3 High Street"]
+    );
+
+    let guests = Guest::builder()
+        .address_with(|a| a.street("Low Road"))
+        .build_many(2);
+    assert!(guests.iter().all(|g| g.address.street == "Low Road"));
+    assert_eq!((guests[0].table, guests[1].table), (10, 11));
+    assert!(Guest::builder().build_many(0).is_empty());
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use boulder::{
+        AsyncBuildable, AsyncBuilder, AsyncGeneratable, AsyncGenerator, Buildable, Builder,
+        Generatable, Generator,
+    };
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    // A minimal executor, so that nothing here depends on a runtime.
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+                return value;
+            }
+        }
+    }
+
+    // A future which is pending once before it completes, as a
+    // stand-in for a call to some service.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    thread_local! {
+        static NEXT_ID: Cell<u64> = const { Cell::new(100) };
+    }
+
+    async fn allocate_id() -> u64 {
+        YieldOnce(false).await;
+        NEXT_ID.with(|id| {
+            let value = id.get();
+            id.set(value + 1);
+            value
+        })
+    }
+
+    #[derive(Debug, Buildable, Generatable)]
+    #[boulder(after_build = |a: &mut Account| a.owner.push('!'))]
+    pub struct Account {
+        #[boulder(default_async = allocate_id())]
+        id: u64,
+        #[boulder(default_async = async { String::from("anon") })]
+        owner: String,
+        #[boulder(default = 10, generator = boulder::Inc(1i32))]
+        balance: i32,
+    }
+
+    #[test]
+    fn test_async_builder() {
+        let a = block_on(async { Account::async_builder().balance(5).build().await });
+        assert!(a.id >= 100);
+        assert_eq!(a.owner, "anon!");
+        assert_eq!(a.balance, 5);
+
+        let b = block_on(Account::async_builder().build());
+        assert_eq!(b.id, a.id + 1);
+        assert_eq!(b.balance, 10);
+
+        // Explicit values are not awaited.
+        let c = block_on(Account::async_builder().id(7u64).owner("Zed").build());
+        assert_eq!(c.id, 7);
+        assert_eq!(c.owner, "Zed!");
+
+        let d = block_on(Box::<Account>::async_builder().id(3u64).build());
+        assert_eq!(d.id, 3);
+        assert_eq!(d.owner, "anon!");
+    }
+
+    // Types without async defaults get both kinds of builder, so
+    // with both traits in scope the method must be named in full.
+    #[derive(Debug, Buildable, Generatable)]
+    pub struct Ledger {
+        #[boulder(default = 3, generator = boulder::Inc(1i32))]
+        entries: i32,
+    }
+
+    #[test]
+    fn test_sync_and_async() {
+        assert_eq!(Builder::build(Ledger::builder()).entries, 3);
+        assert_eq!(
+            block_on(AsyncBuilder::build(Ledger::async_builder())).entries,
+            3
+        );
+
+        let mut g = Ledger::generator();
+        assert_eq!(Generator::generate(&mut g).entries, 1);
+        assert_eq!(block_on(AsyncGenerator::generate(&mut g)).entries, 2);
+        let mut g = Option::<Ledger>::async_generator();
+        assert_eq!(
+            block_on(AsyncGenerator::generate(&mut g)).map(|l| l.entries),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_async_generator() {
+        let mut g = Account::async_generator().owner(boulder::Pattern!("user-{}", boulder::Inc(1)));
+        let (a, b) = block_on(async { (g.generate().await, g.generate().await) });
+        assert_eq!(b.id, a.id + 1);
+        assert_eq!((a.owner.as_str(), a.balance), ("user-1!", 1));
+        assert_eq!((b.owner.as_str(), b.balance), ("user-2!", 2));
+
+        let mut g = Account::async_generator()
+            .id(boulder::Inc(1u64))
+            .owner(|| "sync".to_string());
+        assert_eq!(block_on(g.generate()).id, 1);
+        assert_eq!(block_on(g.generate()).id, 2);
+    }
+
+    #[test]
+    fn test_async_generator_wrappers() {
+        let mut g = Option::<Account>::async_generator().balance(boulder::Inc(7i32));
+        let a = block_on(g.generate()).unwrap();
+        assert_eq!((a.owner.as_str(), a.balance), ("anon!", 7));
+
+        let mut g = Box::<Account>::async_generator().id(boulder::Inc(20u64));
+        let (a, b) = block_on(async { (g.generate().await, g.generate().await) });
+        assert_eq!((a.id, b.id), (20, 21));
+    }
+}
@@ -1,4 +1,4 @@
-#![allow(clippy::blacklisted_name)]
+#![allow(clippy::disallowed_names)]
 
 mod builder_basic {
     use boulder::{BuildableWithPersianRug, BuilderWithPersianRug};
@@ -68,6 +68,79 @@ mod builder_basic {
         println!("Got bar2 {:?}", b1);
         println!("Got baz2 {:?}", z1);
     }
+
+    #[test]
+    fn test_to_builder() {
+        let mut s: State2 = Default::default();
+
+        let (b, _) = persian_rug::Proxy::<Bar2<State2>>::builder()
+            .a(3)
+            .build(&mut s);
+        let builder = <persian_rug::Proxy<Bar2<State2>> as BuildableWithPersianRug<State2>>::Builder::from_proxy(&b, &s);
+        let (b2, _) = builder.a(4).build(&mut s);
+        assert_ne!(b, b2);
+        let original = <State2 as persian_rug::Context>::get(&s, &b);
+        let copy = <State2 as persian_rug::Context>::get(&s, &b2);
+        assert_eq!(original.a, 3);
+        assert_eq!(copy.a, 4);
+        assert_eq!(original.foo, copy.foo);
+
+        let builder = <State2 as persian_rug::Context>::get(&s, &b2).to_proxy_builder();
+        let (b3, _) = builder.build(&mut s);
+        assert_ne!(b2, b3);
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &b3).a, 4);
+
+        let (bar, _) = Bar2::<State2>::builder().a(5).build(&mut s);
+        let foo = bar.foo;
+        let (bar, _) = <Bar2<State2> as BuildableWithPersianRug<State2>>::Builder::from(bar)
+            .foo_with(|f| f.a(6))
+            .build(&mut s);
+        assert_eq!(bar.a, 5);
+        assert_ne!(bar.foo, foo);
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &bar.foo).a, 6);
+
+        let (bar, _) = bar.to_builder().build(&mut s);
+        assert_eq!(bar.a, 5);
+    }
+
+    #[test]
+    fn test_field_with() {
+        let mut s: State2 = Default::default();
+
+        let (z, _) = persian_rug::Proxy::<Baz2<State2>>::builder()
+            .bar_with(|b| b.a(7).foo_with(|f| f.a(9)))
+            .build(&mut s);
+        let bar = <State2 as persian_rug::Context>::get(&s, &z).bar;
+        let b = <State2 as persian_rug::Context>::get(&s, &bar);
+        assert_eq!(b.a, 7);
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &b.foo).a, 9);
+
+        let (b, _) = persian_rug::Proxy::<Bar2<State2>>::builder()
+            .foo_with(|f| f.a(1))
+            .build(&mut s);
+        let foo = <State2 as persian_rug::Context>::get(&s, &b).foo;
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &foo).a, 1);
+
+        let (b, _) = persian_rug::Proxy::<Bar2<State2>>::builder().build(&mut s);
+        let default_foo = <State2 as persian_rug::Context>::get(&s, &b).foo;
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &default_foo).a, 5);
+
+        let (b, _) = persian_rug::Proxy::<Bar2<State2>>::builder()
+            .foo_with(|f| f.a(1))
+            .foo(default_foo)
+            .build(&mut s);
+        assert_eq!(
+            <State2 as persian_rug::Context>::get(&s, &b).foo,
+            default_foo
+        );
+
+        let (b, _) = persian_rug::Proxy::<Bar2<State2>>::builder()
+            .foo(default_foo)
+            .foo_with(|f| f.a(2))
+            .build(&mut s);
+        let foo = <State2 as persian_rug::Context>::get(&s, &b).foo;
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &foo).a, 2);
+    }
 }
 
 mod builder_wrappers {
@@ -76,9 +149,11 @@ mod builder_wrappers {
     use boulder::{BuildableWithPersianRug, BuilderWithPersianRug};
     use persian_rug::Proxy;
     use std::any::Any;
-    use std::cell::{Cell, RefCell};
+    use std::borrow::Cow;
+    use std::cell::{Cell, OnceCell, RefCell};
+    use std::pin::Pin;
     use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, RwLock};
 
     #[persian_rug::contextual(C)]
     #[derive(BuildableWithPersianRug)]
@@ -124,7 +199,7 @@ mod builder_wrappers {
         );
         assert_eq!(
             f1.as_ref()
-                .map(|f1| <State2 as persian_rug::Context>::get(&s, &f1).a),
+                .map(|f1| <State2 as persian_rug::Context>::get(&s, f1).a),
             Some(5)
         );
     }
@@ -189,6 +264,99 @@ mod builder_wrappers {
         );
         assert_eq!(f1.borrow().a, 5);
     }
+
+    #[test]
+    fn test_box() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = Box::<Foo2<State2>>::builder().a(5).build(&mut s);
+        assert_eq!(std::any::TypeId::of::<Box<Foo2<State2>>>(), f1.type_id());
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_pin_box() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = Pin::<Box<Foo2<State2>>>::builder().a(5).build(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<Pin<Box<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_rw_lock() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = RwLock::<Foo2<State2>>::builder().a(5).build(&mut s);
+        assert_eq!(std::any::TypeId::of::<RwLock<Foo2<State2>>>(), f1.type_id());
+        assert_eq!(f1.read().unwrap().a, 5);
+    }
+
+    #[test]
+    fn test_once_cell() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = OnceCell::<Foo2<State2>>::builder().a(5).build(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<OnceCell<Foo2<State2>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.get().unwrap().a, 5);
+    }
+
+    // Cow needs a Clone type, in a context of its own.
+    #[persian_rug::contextual(CowState)]
+    #[derive(BuildableWithPersianRug, Clone)]
+    #[boulder(persian_rug(context=CowState, access(CowFoo)))]
+    struct CowFoo {
+        a: i32,
+    }
+
+    #[derive(Default)]
+    #[persian_rug::persian_rug]
+    struct CowState {
+        #[table]
+        foos: CowFoo,
+    }
+
+    #[test]
+    fn test_cow() {
+        let mut s: CowState = Default::default();
+
+        let (f1, _) = Cow::<'static, CowFoo>::builder().a(5).build(&mut s);
+        assert_eq!(std::any::TypeId::of::<Cow<'static, CowFoo>>(), f1.type_id());
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_arc_rw_lock() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = Arc::<RwLock<Foo2<State2>>>::builder().a(5).build(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<Arc<RwLock<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.read().unwrap().a, 5);
+    }
+
+    #[test]
+    fn test_custom_wrapper() {
+        let mut s: State2 = Default::default();
+
+        let (f1, _) = crate::Tracked::<Proxy<Foo2<State2>>>::builder()
+            .a(5)
+            .build(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<crate::Tracked<Proxy<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.revision, 1);
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &f1.value).a, 5);
+    }
 }
 
 mod builder_coverage {
@@ -1025,6 +1193,293 @@ mod generators_basic {
     }
 }
 
+mod generator_weighted {
+    use boulder::{GeneratableWithPersianRug, GeneratorWithPersianRug};
+    use persian_rug::{contextual, persian_rug, Context, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(GeneratableWithPersianRug)]
+    #[boulder(persian_rug(context=Rug))]
+    struct Tentacle {
+        #[boulder(generator_with_persian_rug=boulder::Weighted!("long" => 3, "short" => 1): boulder::Weighted<String>)]
+        length: String,
+        #[boulder(generator=boulder::Weighted!(1 => 1, 2 => 2))]
+        suckers: i32,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Tentacle);
+
+    #[test]
+    fn test_weighted() {
+        let mut r = Rug(Default::default());
+        let mut g = Proxy::<Tentacle>::generator();
+        let mut lengths = Vec::new();
+        let mut suckers = Vec::new();
+        for _ in 0..12 {
+            let (t, _) = g.generate(&mut r);
+            lengths.push(r.get(&t).length.clone());
+            suckers.push(r.get(&t).suckers);
+        }
+        for window in lengths.chunks(4) {
+            assert_eq!(window.iter().filter(|l| *l == "long").count(), 3);
+        }
+        for window in suckers.chunks(3) {
+            assert_eq!(window.iter().filter(|s| **s == 2).count(), 2);
+        }
+    }
+}
+
+mod generator_auto {
+    use boulder::{GeneratableWithPersianRug, GeneratorWithPersianRug};
+    use persian_rug::{contextual, persian_rug, Context, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(GeneratableWithPersianRug)]
+    #[boulder(persian_rug(context=Rug), auto)]
+    struct Octopus {
+        arms: u8,
+        name: String,
+        awake: Option<bool>,
+        #[boulder(generator=boulder::Inc(10i64))]
+        depth: i64,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Octopus);
+
+    #[test]
+    fn test_auto() {
+        let mut r = Rug(Default::default());
+        let mut g = Proxy::<Octopus>::generator();
+        let (k1, _) = g.generate(&mut r);
+        let (k2, _) = g.generate(&mut r);
+        let (k3, _) = g.generate(&mut r);
+        let k1 = r.get(&k1);
+        assert_eq!(k1.arms, 0);
+        assert_eq!(k1.name, "name-0");
+        assert_eq!(k1.awake, Some(false));
+        assert_eq!(k1.depth, 10);
+        let k2 = r.get(&k2);
+        assert_eq!(k2.arms, 1);
+        assert_eq!(k2.name, "name-1");
+        assert_eq!(k2.awake, None);
+        assert_eq!(k2.depth, 11);
+        let k3 = r.get(&k3);
+        assert_eq!(k3.awake, Some(true));
+    }
+}
+
+mod presets {
+    use boulder::{BuildableWithPersianRug, BuilderWithPersianRug};
+    use boulder::{GeneratableWithPersianRug, GeneratorWithPersianRug};
+    use persian_rug::{contextual, persian_rug, Context, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(BuildableWithPersianRug, GeneratableWithPersianRug)]
+    #[boulder(
+        persian_rug(context=Rug),
+        preset(anaconda, size = 1000u32, name = "Anaconda"),
+        preset(harmless, angry = false)
+    )]
+    struct Adder {
+        #[boulder(default = 10u32, generator = boulder::Inc(10u32))]
+        size: u32,
+        #[boulder(default = "adder", generator = boulder::Pattern!("adder-{}", boulder::Inc(1)))]
+        name: String,
+        #[boulder(default = true)]
+        angry: bool,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Adder);
+
+    #[test]
+    fn test_presets() {
+        let mut r = Rug(Default::default());
+
+        let (s, _) = Proxy::<Adder>::builder()
+            .anaconda()
+            .harmless()
+            .size(7u32)
+            .build(&mut r);
+        let s = r.get(&s);
+        assert_eq!(s.size, 7);
+        assert_eq!(s.name, "Anaconda");
+        assert!(!s.angry);
+
+        let mut g = Proxy::<Adder>::generator().anaconda();
+        let (s1, _) = g.generate(&mut r);
+        let (s2, _) = g.generate(&mut r);
+        assert_eq!(r.get(&s1).size, 1000);
+        assert_eq!(r.get(&s2).size, 1000);
+        assert_eq!(r.get(&s2).name, "Anaconda");
+        assert!(r.get(&s2).angry);
+
+        let mut g = Proxy::<Adder>::generator()
+            .anaconda()
+            .harmless()
+            .size(boulder::Inc(1u32));
+        let (s1, _) = g.generate(&mut r);
+        let (s2, _) = g.generate(&mut r);
+        assert_eq!(r.get(&s1).size, 1);
+        assert_eq!(r.get(&s2).size, 2);
+        assert!(!r.get(&s2).angry);
+    }
+}
+
+mod setters {
+    use boulder::{BuildableWithPersianRug, BuilderWithPersianRug};
+    use persian_rug::{contextual, persian_rug, Context, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(BuildableWithPersianRug)]
+    #[boulder(
+        persian_rug(context=Rug),
+        setter(strip_option),
+        preset(famous, name = "Morgana")
+    )]
+    struct Witch {
+        /// The witch's name, if she has one.
+        #[boulder(default = Some("Agnes".to_string()))]
+        name: Option<String>,
+        #[boulder(default = 2u8, setter(into = false, prefix = "with_"))]
+        cats: u8,
+        #[cfg(any())]
+        broom: String,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Witch);
+
+    #[test]
+    fn test_setters() {
+        let mut r = Rug(Default::default());
+
+        let (w, _) = Proxy::<Witch>::builder()
+            .name("Hilda")
+            .with_cats(3)
+            .build(&mut r);
+        let w = r.get(&w);
+        assert_eq!(w.name.as_deref(), Some("Hilda"));
+        assert_eq!(w.cats, 3);
+
+        let (w, _) = Proxy::<Witch>::builder().unset_name().build(&mut r);
+        assert_eq!(r.get(&w).name, None);
+
+        let (w, _) = Proxy::<Witch>::builder().build(&mut r);
+        assert_eq!(r.get(&w).name.as_deref(), Some("Agnes"));
+
+        let (w, _) = Proxy::<Witch>::builder().famous().build(&mut r);
+        assert_eq!(r.get(&w).name.as_deref(), Some("Morgana"));
+    }
+}
+
+mod transients {
+    use boulder::{BuildableWithPersianRug, BuilderWithPersianRug};
+    use persian_rug::{contextual, persian_rug, Context, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(BuildableWithPersianRug)]
+    #[boulder(persian_rug(context=Rug), transient(large: bool))]
+    struct Coven {
+        #[boulder(default_from = |t| if t.large { 13 } else { 3 })]
+        members: u32,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Coven);
+
+    #[test]
+    fn test_transients() {
+        let mut r = Rug(Default::default());
+
+        let (c, _) = Proxy::<Coven>::builder().build(&mut r);
+        assert_eq!(r.get(&c).members, 3);
+
+        let (c, _) = Proxy::<Coven>::builder().large(true).build(&mut r);
+        assert_eq!(r.get(&c).members, 13);
+
+        let (c, _) = Proxy::<Coven>::builder()
+            .large(true)
+            .members(5u32)
+            .build(&mut r);
+        assert_eq!(r.get(&c).members, 5);
+    }
+}
+
+mod hooks {
+    use boulder::{
+        BuildableWithPersianRug, BuilderWithPersianRug, GeneratableWithPersianRug,
+        GeneratorWithPersianRug,
+    };
+    use persian_rug::{contextual, persian_rug, Context, Mutator, Proxy};
+
+    #[contextual(Rug)]
+    #[derive(BuildableWithPersianRug, GeneratableWithPersianRug)]
+    #[boulder(persian_rug(context=Rug))]
+    struct Parent {
+        children: Vec<Proxy<Child>>,
+    }
+
+    fn count_child(child: &mut Child) {
+        child.position += 1;
+    }
+
+    fn link_child<M>(child: &Proxy<Child>, mut context: M) -> M
+    where
+        M: Mutator<Context = Rug>,
+    {
+        let parent = context.get(child).parent;
+        context.get_mut(&parent).children.push(*child);
+        context
+    }
+
+    #[contextual(Rug)]
+    #[derive(BuildableWithPersianRug, GeneratableWithPersianRug)]
+    #[boulder(
+        persian_rug(context=Rug),
+        after_build = count_child,
+        after_insert_with_persian_rug = link_child
+    )]
+    struct Child {
+        #[boulder(buildable_with_persian_rug, generatable_with_persian_rug)]
+        parent: Proxy<Parent>,
+        position: usize,
+    }
+
+    #[persian_rug]
+    struct Rug(#[table] Parent, #[table] Child);
+
+    #[test]
+    fn test_hooks() {
+        let mut r = Rug(Default::default(), Default::default());
+
+        let (p, _) = Proxy::<Parent>::builder().build(&mut r);
+        let (c1, _) = Proxy::<Child>::builder().parent(p).build(&mut r);
+        let builder = Proxy::<Child>::builder()
+            .parent(p)
+            .after_build(|c| c.position *= 10);
+        let builder = std::thread::spawn(move || builder).join().unwrap();
+        let (c2, _) = builder.build(&mut r);
+        assert_eq!(r.get(&p).children, vec![c1, c2]);
+        assert_eq!(r.get(&c1).position, 1);
+        assert_eq!(r.get(&c2).position, 10);
+
+        // Only proxies are inserted, so only they are linked.
+        let (c, _) = Child::builder().parent(p).build(&mut r);
+        assert_eq!(c.position, 1);
+        assert_eq!(r.get(&p).children.len(), 2);
+
+        let mut g = Proxy::<Child>::generator().after_build(|c| c.position += 1);
+        let (c3, _) = g.generate(&mut r);
+        let (c4, _) = g.generate(&mut r);
+        assert_eq!(r.get(&c3).position, 2);
+        assert_eq!(r.get(&r.get(&c3).parent).children, vec![c3]);
+        assert_eq!(r.get(&r.get(&c4).parent).children, vec![c4]);
+    }
+}
+
 mod generator_coverage {
     use boulder::{Buildable, BuildableWithPersianRug, Builder, BuilderWithPersianRug};
     use boulder::{Generatable, GeneratableWithPersianRug, Generator, GeneratorWithPersianRug};
@@ -2828,9 +3283,11 @@ mod generator_wrappers {
     };
     use persian_rug::Proxy;
     use std::any::Any;
-    use std::cell::{Cell, RefCell};
+    use std::borrow::Cow;
+    use std::cell::{Cell, OnceCell, RefCell};
+    use std::pin::Pin;
     use std::rc::Rc;
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, RwLock};
 
     #[persian_rug::contextual(C)]
     #[derive(GeneratableWithPersianRug)]
@@ -2879,7 +3336,7 @@ mod generator_wrappers {
         );
         assert_eq!(
             f1.as_ref()
-                .map(|f1| <State2 as persian_rug::Context>::get(&s, &f1).a),
+                .map(|f1| <State2 as persian_rug::Context>::get(&s, f1).a),
             Some(5)
         );
     }
@@ -2950,4 +3407,103 @@ mod generator_wrappers {
         );
         assert_eq!(f1.borrow().a, 5);
     }
+
+    #[test]
+    fn test_box() {
+        let mut s: State2 = Default::default();
+
+        let mut g = Box::<Foo2<State2>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(std::any::TypeId::of::<Box<Foo2<State2>>>(), f1.type_id());
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_pin_box() {
+        let mut s: State2 = Default::default();
+
+        let mut g = Pin::<Box<Foo2<State2>>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<Pin<Box<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_rw_lock() {
+        let mut s: State2 = Default::default();
+
+        let mut g = RwLock::<Foo2<State2>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(std::any::TypeId::of::<RwLock<Foo2<State2>>>(), f1.type_id());
+        assert_eq!(f1.read().unwrap().a, 5);
+    }
+
+    #[test]
+    fn test_once_cell() {
+        let mut s: State2 = Default::default();
+
+        let mut g = OnceCell::<Foo2<State2>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<OnceCell<Foo2<State2>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.get().unwrap().a, 5);
+    }
+
+    // Cow needs a Clone type, in a context of its own.
+    #[persian_rug::contextual(CowState)]
+    #[derive(GeneratableWithPersianRug, Clone)]
+    #[boulder(persian_rug(context=CowState, access(CowFoo)))]
+    struct CowFoo {
+        a: i32,
+    }
+
+    #[derive(Default)]
+    #[persian_rug::persian_rug]
+    struct CowState {
+        #[table]
+        foos: CowFoo,
+    }
+
+    #[test]
+    fn test_cow() {
+        let mut s: CowState = Default::default();
+
+        let mut g = Cow::<'static, CowFoo>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(std::any::TypeId::of::<Cow<'static, CowFoo>>(), f1.type_id());
+        assert_eq!(f1.a, 5);
+    }
+
+    #[test]
+    fn test_arc_rw_lock() {
+        let mut s: State2 = Default::default();
+
+        let mut g = Arc::<RwLock<Foo2<State2>>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<Arc<RwLock<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.read().unwrap().a, 5);
+    }
+
+    #[test]
+    fn test_custom_wrapper_generator() {
+        let mut s: State2 = Default::default();
+
+        let mut g =
+            crate::Tracked::<Proxy<Foo2<State2>>>::generator().a(GeneratorWrapper::new(|| 5));
+        let (f1, _) = g.generate(&mut s);
+        assert_eq!(
+            std::any::TypeId::of::<crate::Tracked<Proxy<Foo2<State2>>>>(),
+            f1.type_id()
+        );
+        assert_eq!(f1.revision, 1);
+        assert_eq!(<State2 as persian_rug::Context>::get(&s, &f1.value).a, 5);
+    }
 }